@@ -0,0 +1,285 @@
+use rand::Rng;
+use crate::ecs::EntityId;
+use crate::EntityPosition;
+
+/// What a mob is currently trying to do
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Behaviour {
+    Idle,
+    /// Walking in a direction, -1.0 for left and 1.0 for right
+    Wander(f32),
+    Chase(EntityId),
+    Flee(EntityId),
+}
+
+/// How a mob reacts to the player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Temperament {
+    /// Chases the player when it is within sight
+    Hostile,
+    /// Runs away from the player when it is within sight
+    Passive,
+}
+
+/// How a mob moves once it has decided where to go
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gait {
+    Walk,
+    /// Only moves while grounded, by jumping with the given upwards velocity
+    Hop(f32),
+}
+
+/// The state machine driving a mob, stored as a component
+#[derive(Clone, Debug)]
+pub struct Brain {
+    pub temperament: Temperament,
+    pub gait: Gait,
+    pub speed: f32,
+    /// Distance in pixels at which the player is noticed
+    pub sight_range: f32,
+    state: Behaviour,
+    /// Ticks until an idle or wandering mob picks a new idle behaviour
+    ticks_left: u32,
+}
+
+/// What a mob can see this tick
+pub struct Senses {
+    pub pos: EntityPosition,
+    /// The closest player and its position, if any
+    pub target: Option<(EntityId, EntityPosition)>,
+    pub grounded: bool,
+}
+
+/// The movement a mob wants to make this tick, as a velocity push
+#[derive(Clone, Debug, PartialEq)]
+pub struct Steering {
+    pub push: (f32, f32),
+}
+
+const MIN_IDLE_TICKS: u32 = 20;
+const MAX_IDLE_TICKS: u32 = 90;
+
+impl Brain {
+    pub fn new(temperament: Temperament, gait: Gait, speed: f32, sight_range: f32) -> Brain {
+        Brain {
+            temperament,
+            gait,
+            speed,
+            sight_range,
+            state: Behaviour::Idle,
+            ticks_left: 0,
+        }
+    }
+
+    pub fn state(&self) -> Behaviour {
+        self.state
+    }
+
+    /// Picks the behaviour for this tick. Seeing a player always wins over idling.
+    pub fn think(&mut self, senses: &Senses, rng: &mut impl Rng) -> Behaviour {
+        let visible = senses.target.as_ref()
+            .filter(|(_, pos)| distance(&senses.pos, pos) <= self.sight_range);
+        if let Some((id, _)) = visible {
+            self.state = match self.temperament {
+                Temperament::Hostile => Behaviour::Chase(*id),
+                Temperament::Passive => Behaviour::Flee(*id),
+            };
+            self.ticks_left = 0;
+            return self.state;
+        }
+
+        if matches!(self.state, Behaviour::Chase(_) | Behaviour::Flee(_)) || self.ticks_left == 0 {
+            self.state = if rng.gen_bool(0.5) {
+                Behaviour::Idle
+            } else if rng.gen_bool(0.5) {
+                Behaviour::Wander(-1.0)
+            } else {
+                Behaviour::Wander(1.0)
+            };
+            self.ticks_left = rng.gen_range(MIN_IDLE_TICKS..=MAX_IDLE_TICKS);
+        }
+        self.ticks_left -= 1;
+        self.state
+    }
+
+    /// Turns the current behaviour into movement
+    pub fn steer(&self, senses: &Senses) -> Steering {
+        let direction = match self.state {
+            Behaviour::Idle => 0.0,
+            Behaviour::Wander(dir) => dir,
+            Behaviour::Chase(_) => senses.target.as_ref().map_or(0.0, |(_, pos)| (pos.0 - senses.pos.0).signum()),
+            Behaviour::Flee(_) => senses.target.as_ref().map_or(0.0, |(_, pos)| (senses.pos.0 - pos.0).signum()),
+        };
+        if direction == 0.0 {
+            return Steering { push: (0.0, 0.0) };
+        }
+        match self.gait {
+            Gait::Walk => Steering { push: (direction * self.speed, 0.0) },
+            Gait::Hop(strength) if senses.grounded => Steering { push: (direction * self.speed, strength) },
+            Gait::Hop(_) => Steering { push: (0.0, 0.0) },
+        }
+    }
+}
+
+pub fn distance(a: &EntityPosition, b: &EntityPosition) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::coords;
+    use crate::ecs::component::{Collider, Position};
+    use crate::ecs::Ecs;
+    use crate::entity::{self, EntityType};
+    use crate::tile::base::BaseTile;
+    use crate::world::World;
+    use crate::{TilePosition, TILE_SIZE};
+    use super::*;
+
+    /// A world with a bedrock floor, a stone wall filling column `wall` if given, and nothing spawning
+    fn floor_world(wall: Option<usize>) -> Rc<RefCell<World>> {
+        let world = World::build(30, 8, 1, |world| {
+            for x in 0..30 {
+                world.set_tile(&TilePosition(x, 0), BaseTile::named("bedrock").unwrap());
+            }
+            if let Some(wall) = wall {
+                for y in 1..8 {
+                    world.set_tile(&TilePosition(wall, y), BaseTile::named("stone").unwrap());
+                }
+            }
+        });
+        world.borrow_mut().set_spawn_rules(vec![]);
+        world
+    }
+
+    /// Spawns an entity standing on the floor at tile column `x`
+    fn spawn_at(world: &Rc<RefCell<World>>, kind: EntityType, x: usize) -> EntityId {
+        entity::spawn(&mut world.borrow_mut(), kind, coords::tile_to_world(&TilePosition(x, 1)))
+    }
+
+    fn run(world: &Rc<RefCell<World>>, ticks: usize) {
+        for _ in 0..ticks {
+            world.borrow_mut().update();
+        }
+    }
+
+    fn x_of(world: &Rc<RefCell<World>>, id: EntityId) -> f32 {
+        world.borrow().ecs().get::<Position>(id).unwrap().0.0
+    }
+
+    fn state_of(world: &Rc<RefCell<World>>, id: EntityId) -> Behaviour {
+        world.borrow().ecs().get::<Brain>(id).unwrap().state()
+    }
+
+    fn senses(pos: f32, target: Option<(EntityId, f32)>, grounded: bool) -> Senses {
+        Senses {
+            pos: EntityPosition(pos, 0.0),
+            target: target.map(|(id, x)| (id, EntityPosition(x, 0.0))),
+            grounded,
+        }
+    }
+
+    #[test]
+    fn notices_players_in_sight() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let player = Ecs::new().spawn();
+        let mut brain = Brain::new(Temperament::Hostile, Gait::Walk, 2.0, 100.0);
+        assert_eq!(brain.state(), Behaviour::Idle);
+        // Out of sight it keeps idling or wandering
+        let state = brain.think(&senses(0.0, Some((player, 150.0)), true), &mut rng);
+        assert!(matches!(state, Behaviour::Idle | Behaviour::Wander(_)));
+        assert_eq!(brain.think(&senses(0.0, Some((player, 80.0)), true), &mut rng), Behaviour::Chase(player));
+        brain.temperament = Temperament::Passive;
+        assert_eq!(brain.think(&senses(0.0, Some((player, 80.0)), true), &mut rng), Behaviour::Flee(player));
+        assert_eq!(brain.state(), Behaviour::Flee(player));
+    }
+
+    #[test]
+    fn forgets_players_out_of_sight() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let player = Ecs::new().spawn();
+        let mut brain = Brain::new(Temperament::Hostile, Gait::Walk, 2.0, 100.0);
+        brain.think(&senses(0.0, Some((player, 50.0)), true), &mut rng);
+        let state = brain.think(&senses(0.0, Some((player, 500.0)), true), &mut rng);
+        assert!(matches!(state, Behaviour::Idle | Behaviour::Wander(_)));
+        let state = brain.think(&senses(0.0, None, true), &mut rng);
+        assert!(matches!(state, Behaviour::Idle | Behaviour::Wander(_)));
+    }
+
+    #[test]
+    fn steers_towards_and_away_from_target() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let player = Ecs::new().spawn();
+        let mut brain = Brain::new(Temperament::Hostile, Gait::Walk, 2.0, 100.0);
+        for (target, direction) in [(60.0, 1.0), (-60.0, -1.0)] {
+            let senses = senses(0.0, Some((player, target)), true);
+            brain.temperament = Temperament::Hostile;
+            brain.think(&senses, &mut rng);
+            assert_eq!(brain.steer(&senses), Steering { push: (direction * 2.0, 0.0) });
+            brain.temperament = Temperament::Passive;
+            brain.think(&senses, &mut rng);
+            assert_eq!(brain.steer(&senses), Steering { push: (-direction * 2.0, 0.0) });
+        }
+    }
+
+    #[test]
+    fn hops_only_when_grounded() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let player = Ecs::new().spawn();
+        let mut brain = Brain::new(Temperament::Hostile, Gait::Hop(5.0), 1.5, 100.0);
+        brain.think(&senses(0.0, Some((player, 60.0)), true), &mut rng);
+        assert_eq!(brain.steer(&senses(0.0, Some((player, 60.0)), true)), Steering { push: (1.5, 5.0) });
+        assert_eq!(brain.steer(&senses(0.0, Some((player, 60.0)), false)), Steering { push: (0.0, 0.0) });
+    }
+
+    #[test]
+    fn idle_does_not_move() {
+        let brain = Brain::new(Temperament::Hostile, Gait::Walk, 2.0, 100.0);
+        assert_eq!(brain.steer(&senses(0.0, None, true)), Steering { push: (0.0, 0.0) });
+    }
+
+    #[test]
+    fn slimes_chase_players_in_a_world() {
+        let world = floor_world(None);
+        let player = spawn_at(&world, EntityType::Player, 9);
+        let slime = spawn_at(&world, EntityType::Slime, 5);
+        let start = x_of(&world, slime);
+        run(&world, 20);
+        assert_eq!(state_of(&world, slime), Behaviour::Chase(player));
+        assert!(x_of(&world, slime) > start);
+    }
+
+    #[test]
+    fn critters_flee_players_in_a_world() {
+        let world = floor_world(None);
+        let player = spawn_at(&world, EntityType::Player, 8);
+        let critter = spawn_at(&world, EntityType::Critter, 10);
+        let start = x_of(&world, critter);
+        run(&world, 20);
+        assert_eq!(state_of(&world, critter), Behaviour::Flee(player));
+        assert!(x_of(&world, critter) > start);
+    }
+
+    #[test]
+    fn steering_stops_at_walls() {
+        let world = floor_world(Some(7));
+        let player = spawn_at(&world, EntityType::Player, 9);
+        let slime = spawn_at(&world, EntityType::Slime, 5);
+        let start = x_of(&world, slime);
+        run(&world, 60);
+        assert_eq!(state_of(&world, slime), Behaviour::Chase(player));
+        let width = world.borrow().ecs().get::<Collider>(slime).unwrap().width;
+        let x = x_of(&world, slime);
+        assert!(x > start);
+        // Pressed against the wall's left edge without crossing it
+        assert_eq!(x + width, 7.0 * TILE_SIZE);
+        let world = world.borrow();
+        let pos = &world.ecs().get::<Position>(slime).unwrap().0;
+        assert!(world.is_occupied(&pos.offset((width, 0.0)).to_tile_coords()));
+        assert!(!world.is_occupied(&pos.to_tile_coords()));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::coords::ViewTransform;
+use crate::EntityPosition;
+
+/// How far the camera moves towards its target each tick, between 0 and 1
+const FOLLOW_SMOOTHING: f32 = 0.15;
+/// The zoom levels the mouse wheel steps through
+const ZOOM_LEVELS: [f32; 7] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 3.0];
+const DEFAULT_ZOOM_LEVEL: usize = 2;
+
+/// Looks at part of the world. Positions are in world pixels with y going up.
+pub struct Camera {
+    pub center: EntityPosition,
+    zoom_level: usize,
+    /// Size of the area drawn to, in screen pixels
+    viewport: (f32, f32),
+}
+
+impl Camera {
+    pub fn new(viewport: (usize, usize)) -> Camera {
+        Camera {
+            center: EntityPosition(viewport.0 as f32 / 2.0, viewport.1 as f32 / 2.0),
+            zoom_level: DEFAULT_ZOOM_LEVEL,
+            viewport: (viewport.0 as f32, viewport.1 as f32),
+        }
+    }
+
+    pub fn zoom(&self) -> f32 {
+        ZOOM_LEVELS[self.zoom_level]
+    }
+
+    /// Steps the zoom level up (positive) or down (negative), staying within the available levels
+    pub fn step_zoom(&mut self, steps: isize) {
+        self.zoom_level = self.zoom_level.saturating_add_signed(steps).min(ZOOM_LEVELS.len() - 1);
+    }
+
+    /// Resizes the area drawn to, such as after the window was resized
+    pub fn set_viewport(&mut self, viewport: (usize, usize)) {
+        self.viewport = (viewport.0 as f32, viewport.1 as f32);
+    }
+
+    /// Moves part of the way towards `target`, keeping the view inside the world
+    pub fn follow(&mut self, target: &EntityPosition, world_size: (f32, f32)) {
+        self.center.0 += (target.0 - self.center.0) * FOLLOW_SMOOTHING;
+        self.center.1 += (target.1 - self.center.1) * FOLLOW_SMOOTHING;
+        self.clamp(world_size);
+    }
+
+    /// Keeps the view inside the world, or centered on it when the world is smaller than the view
+    pub fn clamp(&mut self, world_size: (f32, f32)) {
+        let half = (self.viewport.0 / self.zoom() / 2.0, self.viewport.1 / self.zoom() / 2.0);
+        self.center.0 = clamp_axis(self.center.0, half.0, world_size.0);
+        self.center.1 = clamp_axis(self.center.1, half.1, world_size.1);
+    }
+
+    pub fn transform(&self) -> ViewTransform {
+        let zoom = self.zoom();
+        ViewTransform::new(zoom,
+                           self.center.0 - self.viewport.0 / zoom / 2.0,
+                           self.center.1 + self.viewport.1 / zoom / 2.0,
+                           self.viewport)
+    }
+}
+
+fn clamp_axis(center: f32, half_view: f32, world_size: f32) -> f32 {
+    if half_view * 2.0 >= world_size {
+        world_size / 2.0
+    } else {
+        center.clamp(half_view, world_size - half_view)
+    }
+}
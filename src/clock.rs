@@ -0,0 +1,118 @@
+use std::f32::consts::TAU;
+use raqote::{Color, DrawOptions, DrawTarget, Gradient, GradientStop, Point, Source, Spread};
+
+/// Ticks in a full day, 4 minutes at the default tick rate
+pub const DEFAULT_DAY_LENGTH: u64 = 7200;
+/// Fraction of the day new worlds start at, in the morning once it is fully light
+const START_TIME_OF_DAY: f32 = 0.35;
+/// How much of the sky light is left in the middle of the night
+const NIGHT_SKY_INTENSITY: f32 = 0.2;
+
+/// Sky colours as RGB
+type Rgb = [u8; 3];
+const DAY_TOP: Rgb = [0x5b, 0x9b, 0xe6];
+const DAY_BOTTOM: Rgb = [0xb4, 0xdc, 0xf8];
+const NIGHT_TOP: Rgb = [0x05, 0x07, 0x18];
+const NIGHT_BOTTOM: Rgb = [0x14, 0x1c, 0x3c];
+const SUNSET: Rgb = [0xf0, 0x8c, 0x50];
+
+/// The time of a world, counted in ticks since it was created.
+/// 0.0 into the day is midnight, 0.25 sunrise, 0.5 noon and 0.75 sunset.
+#[derive(Clone, Debug)]
+pub struct WorldClock {
+    time: u64,
+    day_length: u64,
+}
+
+impl WorldClock {
+    pub fn new(day_length: u64) -> WorldClock {
+        let day_length = day_length.max(1);
+        WorldClock {
+            time: (day_length as f32 * START_TIME_OF_DAY) as u64,
+            day_length,
+        }
+    }
+
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
+
+    pub fn day_length(&self) -> u64 {
+        self.day_length
+    }
+
+    /// Changes the length of a day, keeping the current day and time of day
+    pub fn set_day_length(&mut self, day_length: u64) {
+        let time_of_day = self.time_of_day();
+        let day = self.day();
+        self.day_length = day_length.max(1);
+        self.time = day * self.day_length + (time_of_day * self.day_length as f32) as u64;
+    }
+
+    /// Moves to a point in the current day, from 0 to 1
+    pub fn set_time_of_day(&mut self, time_of_day: f32) {
+        self.time = self.day() * self.day_length + (time_of_day.rem_euclid(1.0) * self.day_length as f32) as u64;
+    }
+
+    pub fn advance(&mut self) {
+        self.time += 1;
+    }
+
+    /// Days passed since the world was created
+    pub fn day(&self) -> u64 {
+        self.time / self.day_length
+    }
+
+    /// How far into the current day it is, from 0 to 1
+    pub fn time_of_day(&self) -> f32 {
+        (self.time % self.day_length) as f32 / self.day_length as f32
+    }
+
+    /// Height of the sun from -1 at midnight to 1 at noon
+    fn sun_height(&self) -> f32 {
+        -(self.time_of_day() * TAU).cos()
+    }
+
+    /// How strong sky light is right now, from `NIGHT_SKY_INTENSITY` at night to 1 during the day
+    pub fn sky_intensity(&self) -> f32 {
+        let daylight = ((self.sun_height() + 0.2) / 0.6).clamp(0.0, 1.0);
+        NIGHT_SKY_INTENSITY + (1.0 - NIGHT_SKY_INTENSITY) * daylight
+    }
+
+    /// Fills the target with the sky gradient for the time of day
+    pub fn render_sky(&self, target: &mut DrawTarget) {
+        let daylight = (self.sky_intensity() - NIGHT_SKY_INTENSITY) / (1.0 - NIGHT_SKY_INTENSITY);
+        // The horizon glows while the sun is close to it
+        let glow = (1.0 - self.sun_height().abs() / 0.3).clamp(0.0, 1.0);
+        let top = color(lerp(NIGHT_TOP, DAY_TOP, daylight));
+        let bottom = color(lerp(lerp(NIGHT_BOTTOM, DAY_BOTTOM, daylight), SUNSET, glow * 0.7));
+        let (width, height) = (target.width() as f32, target.height() as f32);
+        let gradient = Gradient {
+            stops: vec![
+                GradientStop { position: 0.0, color: top },
+                GradientStop { position: 1.0, color: bottom },
+            ],
+        };
+        let source = Source::new_linear_gradient(gradient, Point::new(0.0, 0.0), Point::new(0.0, height), Spread::Pad);
+        target.fill_rect(0.0, 0.0, width, height, &source, &DrawOptions::new());
+    }
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        WorldClock::new(DEFAULT_DAY_LENGTH)
+    }
+}
+
+fn lerp(from: Rgb, to: Rgb, t: f32) -> Rgb {
+    let mix = |i: usize| (from[i] as f32 + (to[i] as f32 - from[i] as f32) * t).round() as u8;
+    [mix(0), mix(1), mix(2)]
+}
+
+fn color([r, g, b]: Rgb) -> Color {
+    Color::new(0xff, r, g, b)
+}
@@ -0,0 +1,247 @@
+pub mod commands;
+
+use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+use crate::ecs::EntityId;
+use crate::plugin;
+use crate::script::ScriptHost;
+use crate::world::World;
+
+/// Lines kept in the console log, older ones are dropped
+const MAX_LOG_LINES: usize = 200;
+
+/// What commands can act on
+pub struct CommandContext<'a> {
+    pub world: &'a Rc<RefCell<World>>,
+    pub player: EntityId,
+    /// Runs script hooks, and the commands scripts registered
+    pub scripts: &'a ScriptHost,
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    Unknown(String),
+    /// Wrong number of arguments, holds the command's usage
+    Usage(String),
+    InvalidArgument { name: &'static str, value: String },
+    Failed(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Unknown(name) => write!(f, "unknown command '{}', try /help", name),
+            CommandError::Usage(usage) => write!(f, "usage: {}", usage),
+            CommandError::InvalidArgument { name, value } => write!(f, "invalid {} '{}'", name, value),
+            CommandError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+pub type CommandResult = Result<String, CommandError>;
+pub type CommandFn = Box<dyn Fn(&mut CommandContext, &Arguments) -> CommandResult>;
+
+/// The words after a command's name
+pub struct Arguments<'a> {
+    words: Vec<&'a str>,
+    usage: &'a str,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        self.words.get(index).copied()
+    }
+
+    /// The error for arguments that don't match the usage
+    pub fn usage_error(&self) -> CommandError {
+        CommandError::Usage(self.usage.to_string())
+    }
+
+    /// The argument at `index` parsed as T, `name` describing it in the error
+    pub fn parse<T: FromStr>(&self, index: usize, name: &'static str) -> Result<T, CommandError> {
+        let word = self.get(index).ok_or_else(|| self.usage_error())?;
+        word.parse().map_err(|_| CommandError::InvalidArgument { name, value: word.to_string() })
+    }
+
+    /// Like `parse`, None when the argument was left out
+    pub fn parse_opt<T: FromStr>(&self, index: usize, name: &'static str) -> Result<Option<T>, CommandError> {
+        match self.get(index) {
+            Some(_) => self.parse(index, name).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+pub struct Command {
+    pub name: String,
+    /// Shown for `/help` and on usage errors, such as "tp <x> <y>"
+    pub usage: String,
+    pub help: String,
+    /// How many arguments the command takes
+    pub arity: RangeInclusive<usize>,
+    run: CommandFn,
+}
+
+impl Command {
+    pub fn new(name: &str, usage: &str, help: &str, arity: RangeInclusive<usize>,
+               run: impl Fn(&mut CommandContext, &Arguments) -> CommandResult + 'static) -> Command {
+        Command {
+            name: name.to_string(),
+            usage: usage.to_string(),
+            help: help.to_string(),
+            arity,
+            run: Box::new(run),
+        }
+    }
+}
+
+/// Commands by name
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+}
+
+impl CommandRegistry {
+    /// A registry holding the built in commands and those of plugins
+    pub fn new() -> CommandRegistry {
+        let mut registry = CommandRegistry::default();
+        commands::register(&mut registry);
+        plugin::register_commands(&mut registry);
+        registry
+    }
+
+    /// Adds a command, returning false if one already has its name
+    pub fn register(&mut self, command: Command) -> bool {
+        if self.commands.contains_key(&command.name) {
+            return false;
+        }
+        self.commands.insert(command.name.clone(), command);
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.values()
+    }
+
+    /// Runs a line such as "/tp 4 10", the leading slash being optional
+    pub fn run(&self, line: &str, ctx: &mut CommandContext) -> CommandResult {
+        let mut words = line.trim().trim_start_matches('/').split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(String::new());
+        };
+        if name == "help" {
+            let args = Arguments { words: words.collect(), usage: commands::HELP_USAGE };
+            return commands::help(self, &args);
+        }
+        let command = self.get(name).ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+        let args = Arguments { words: words.collect(), usage: &command.usage };
+        if !command.arity.contains(&args.len()) {
+            return Err(CommandError::Usage(command.usage.clone()));
+        }
+        (command.run)(ctx, &args)
+    }
+
+    /// Runs every line of a script file, skipping blank lines and # comments.
+    /// Returns what each line printed, errors included.
+    pub fn run_script(&self, path: &Path, ctx: &mut CommandContext) -> std::io::Result<Vec<String>> {
+        let script = fs::read_to_string(path)?;
+        Ok(script.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match self.run(line, ctx) {
+                Ok(output) => output,
+                Err(e) => format!("{}: {}", line, e),
+            })
+            .filter(|output| !output.is_empty())
+            .collect())
+    }
+}
+
+/// The console's text entry and what it printed
+pub struct Console {
+    pub registry: CommandRegistry,
+    pub input: String,
+    log: Vec<String>,
+    /// Lines entered before, newest last
+    history: Vec<String>,
+    /// Position in `history` while browsing it with up and down
+    history_pos: Option<usize>,
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console {
+            registry: CommandRegistry::new(),
+            input: String::new(),
+            log: vec![],
+            history: vec![],
+            history_pos: None,
+        }
+    }
+
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.log.push(line.into());
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    /// Takes the entered line, remembering it in the history
+    pub fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.input);
+        self.history_pos = None;
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        line
+    }
+
+    /// Replaces the input with an older (`back`) or newer line from the history
+    pub fn browse_history(&mut self, back: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let pos = match (self.history_pos, back) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(pos), true) => Some(pos.saturating_sub(1)),
+            (Some(pos), false) => Some(pos + 1).filter(|&pos| pos < self.history.len()),
+        };
+        self.history_pos = pos;
+        self.input = pos.map_or(String::new(), |pos| self.history[pos].clone());
+    }
+
+    /// Runs a line, logging it and its output
+    pub fn execute(&mut self, line: &str, ctx: &mut CommandContext) {
+        self.print(format!("> {}", line));
+        match self.registry.run(line, ctx) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => output.lines().for_each(|line| self.print(line)),
+            Err(e) => self.print(format!("error: {}", e)),
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Console::new()
+    }
+}
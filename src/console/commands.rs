@@ -0,0 +1,282 @@
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use crate::console::{Arguments, Command, CommandContext, CommandError, CommandRegistry, CommandResult};
+use crate::coords;
+use crate::ecs::component::{Health, Position, Velocity};
+use crate::entity;
+use crate::plugin;
+use crate::tile::entity::{ItemStack, TileEntity, MAX_STACK};
+use crate::tile::meta::{Orientation, MAX_GROWTH};
+use crate::tile::{self, Tile};
+use crate::world::World;
+use crate::{FloatVector2D, TilePosition, TICK_RATE};
+
+/// Most tiles a single /fill may change
+const MAX_FILL: usize = 4096;
+/// Most ticks a single /tick may advance
+const MAX_TICKS: u64 = 100_000;
+
+/// Adds the built in commands
+pub fn register(registry: &mut CommandRegistry) {
+    registry.register(Command::new("tp", "tp <x> <y>", "Moves the player to a tile", 2..=2, tp));
+    registry.register(Command::new("setblock", "setblock <x> <y> <tile>", "Places a tile, such as stone, air or example:sand", 3..=3, setblock));
+    registry.register(Command::new("fill", "fill <x1> <y1> <x2> <y2> <tile>", "Places tiles over an area", 5..=5, fill));
+    registry.register(Command::new("spawn", "spawn <entity> [x y]", "Spawns an entity, next to the player by default", 1..=3, spawn));
+    registry.register(Command::new("heal", "heal", "Restores the player's health", 0..=0, heal));
+    registry.register(Command::new("time", "time [set <ticks|day|noon|sunset|night|midnight> | add <ticks>]", "Shows or changes the time", 0..=2, time));
+    registry.register(Command::new("seed", "seed", "Shows the world seed", 0..=0, seed));
+    registry.register(Command::new("tickrate", "tickrate [ticks per second]", "Shows or changes the tick rate", 0..=1, tickrate));
+    registry.register(Command::new("tick", "tick [count]", "Advances the world, one tick by default", 0..=1, tick));
+    registry.register(Command::new("tiledata", "tiledata <x> <y>", "Shows a tile's metadata and tile entity", 2..=2, tiledata));
+    registry.register(Command::new("meta", "meta <x> <y> <orientation|growth|damage> <value>", "Changes a tile's metadata", 4..=4, meta));
+    registry.register(Command::new("sign", "sign <x> <y> <text>", "Writes on a sign", 3..=usize::MAX, sign));
+    registry.register(Command::new("put", "put <x> <y> <item> [count]", "Puts items into a chest or furnace", 3..=4, put));
+    registry.register(Command::new("plugins", "plugins", "Lists the loaded plugins in load order", 0..=0, plugins));
+    registry.register(Command::new("recipes", "recipes", "Lists the recipes plugins added", 0..=0, recipes));
+}
+
+pub const HELP_USAGE: &str = "help [command]";
+
+/// Lists the commands, or describes one. It needs the registry, so `CommandRegistry::run` calls it directly.
+pub fn help(registry: &CommandRegistry, args: &Arguments) -> CommandResult {
+    match args.get(0) {
+        _ if args.len() > 1 => Err(args.usage_error()),
+        Some("help") | Some("/help") => Ok(format!("/{} - Lists the commands or describes one", HELP_USAGE)),
+        Some(name) => {
+            let command = registry.get(name.trim_start_matches('/')).ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+            Ok(format!("/{} - {}", command.usage, command.help))
+        }
+        None => {
+            let names: Vec<String> = registry.iter().map(|command| format!("/{}", command.name)).collect();
+            Ok(format!("/help {}", names.join(" ")))
+        }
+    }
+}
+
+/// Parses a tile position from two arguments, checking it is in the world
+fn tile_arg(world: &World, args: &Arguments, index: usize) -> Result<TilePosition, CommandError> {
+    let x: usize = args.parse(index, "x")?;
+    let y: usize = args.parse(index + 1, "y")?;
+    if x >= world.width() || y >= world.height() {
+        return Err(CommandError::Failed(format!("({},{}) is outside of the {}x{} world", x, y, world.width(), world.height())));
+    }
+    Ok(TilePosition(x, y))
+}
+
+/// A tile from its name, checking the name is known
+fn tile_name_arg<'a>(args: &Arguments<'a>, index: usize) -> Result<&'a str, CommandError> {
+    let name = args.get(index).ok_or_else(|| args.usage_error())?;
+    match tile::from_name(name) {
+        Some(_) => Ok(name),
+        None => Err(CommandError::InvalidArgument { name: "tile", value: name.to_string() }),
+    }
+}
+
+fn new_tile(name: &str) -> Box<dyn Tile> {
+    tile::from_name(name).expect("tile name was checked")
+}
+
+fn tp(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let world = ctx.world.borrow();
+    let pos = tile_arg(&world, args, 0)?;
+    let ecs = world.ecs();
+    let mut position = ecs.get_mut::<Position>(ctx.player).ok_or_else(|| CommandError::Failed("there is no player".to_string()))?;
+    position.0 = coords::tile_to_world(&pos);
+    if let Some(mut vel) = ecs.get_mut::<Velocity>(ctx.player) {
+        vel.0 = FloatVector2D(0.0, 0.0);
+    }
+    Ok(format!("teleported to {}", pos))
+}
+
+fn setblock(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let pos = tile_arg(&world, args, 0)?;
+    let name = tile_name_arg(args, 2)?;
+    world.set_tile(&pos, new_tile(name));
+    Ok(format!("set {} to {}", pos, name))
+}
+
+fn fill(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let a = tile_arg(&world, args, 0)?;
+    let b = tile_arg(&world, args, 2)?;
+    let name = tile_name_arg(args, 4)?;
+    let (xs, ys) = (a.0.min(b.0)..=a.0.max(b.0), a.1.min(b.1)..=a.1.max(b.1));
+    let count = xs.clone().count() * ys.clone().count();
+    if count > MAX_FILL {
+        return Err(CommandError::Failed(format!("{} tiles is more than the limit of {}", count, MAX_FILL)));
+    }
+    for y in ys {
+        for x in xs.clone() {
+            world.set_tile(&TilePosition(x, y), new_tile(name));
+        }
+    }
+    Ok(format!("filled {} tiles with {}", count, name))
+}
+
+fn spawn(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let name = args.get(0).ok_or_else(|| args.usage_error())?;
+    let mut world = ctx.world.borrow_mut();
+    let pos = match args.len() {
+        1 => {
+            let player = world.ecs().get::<Position>(ctx.player).map(|p| p.0.clone());
+            let player = player.ok_or_else(|| CommandError::Failed("there is no player to spawn next to".to_string()))?;
+            player.offset((crate::TILE_SIZE * 2.0, 0.0))
+        }
+        3 => coords::tile_to_world(&tile_arg(&world, args, 1)?),
+        _ => return Err(args.usage_error()),
+    };
+    let id = entity::spawn_named(&mut world, name, pos)
+        .ok_or_else(|| CommandError::InvalidArgument { name: "entity", value: name.to_string() })?;
+    Ok(format!("spawned {} {}", name, id))
+}
+
+fn heal(ctx: &mut CommandContext, _args: &Arguments) -> CommandResult {
+    let world = ctx.world.borrow();
+    let mut health = world.ecs().get_mut::<Health>(ctx.player)
+        .ok_or_else(|| CommandError::Failed("there is no player".to_string()))?;
+    health.current = health.max;
+    Ok(format!("healed to {}", health.max))
+}
+
+/// Named points in the day accepted by /time set
+struct TimeOfDay(f32);
+
+impl FromStr for TimeOfDay {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "midnight" => Ok(TimeOfDay(0.0)),
+            "day" => Ok(TimeOfDay(0.35)),
+            "noon" => Ok(TimeOfDay(0.5)),
+            "sunset" => Ok(TimeOfDay(0.75)),
+            "night" => Ok(TimeOfDay(0.85)),
+            _ => Err(()),
+        }
+    }
+}
+
+fn time(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let clock = world.clock_mut();
+    match (args.get(0), args.len()) {
+        (None, _) => {}
+        (Some("set"), 2) => match args.parse::<u64>(1, "time") {
+            Ok(ticks) => clock.set_time(ticks),
+            Err(_) => clock.set_time_of_day(args.parse::<TimeOfDay>(1, "time")?.0),
+        },
+        (Some("add"), 2) => {
+            let ticks = args.parse::<u64>(1, "ticks")?;
+            clock.set_time(clock.time().saturating_add(ticks));
+        }
+        _ => return Err(args.usage_error()),
+    }
+    Ok(format!("time is {} (day {}, {:.0}% through)", clock.time(), clock.day(), clock.time_of_day() * 100.0))
+}
+
+fn seed(ctx: &mut CommandContext, _args: &Arguments) -> CommandResult {
+    Ok(format!("seed is {}", ctx.world.borrow().seed()))
+}
+
+fn tickrate(_ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    if let Some(rate) = args.parse_opt::<u8>(0, "tick rate")? {
+        if rate == 0 {
+            return Err(CommandError::InvalidArgument { name: "tick rate", value: rate.to_string() });
+        }
+        TICK_RATE.store(rate, Ordering::Relaxed);
+    }
+    Ok(format!("tick rate is {} per second", TICK_RATE.load(Ordering::Relaxed)))
+}
+
+fn tick(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let count = args.parse_opt::<u64>(0, "count")?.unwrap_or(1);
+    if count > MAX_TICKS {
+        return Err(CommandError::Failed(format!("{} ticks is more than the limit of {}", count, MAX_TICKS)));
+    }
+    // Through the script host so scripted tiles and behaviours advance too
+    for _ in 0..count {
+        ctx.scripts.tick(ctx.world, ctx.player);
+    }
+    Ok(format!("advanced {} ticks", count))
+}
+
+fn plugins(_ctx: &mut CommandContext, _args: &Arguments) -> CommandResult {
+    let plugins = plugin::load_order();
+    if plugins.is_empty() {
+        return Ok("no plugins are loaded".to_string());
+    }
+    Ok(format!("plugins: {}", plugins.join(", ")))
+}
+
+fn recipes(_ctx: &mut CommandContext, _args: &Arguments) -> CommandResult {
+    let recipes: Vec<String> = plugin::recipes().map(|recipe| {
+        let inputs: Vec<String> = recipe.inputs.iter().map(|(id, count)| format!("{} {}", count, id)).collect();
+        format!("{}: {} -> {} {}", recipe.id, inputs.join(" + "), recipe.output.1, recipe.output.0)
+    }).collect();
+    if recipes.is_empty() {
+        return Ok("there are no recipes".to_string());
+    }
+    Ok(recipes.join("\n"))
+}
+
+fn tiledata(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let world = ctx.world.borrow();
+    let pos = tile_arg(&world, args, 0)?;
+    let name = world.get_tile(&pos).and_then(|t| tile::name_of(t.as_ref())).unwrap_or_else(|| "-".to_string());
+    let meta = world.meta(&pos);
+    let mut output = format!("{} at {}: orientation={} growth={} damage={}", name, pos, meta.orientation, meta.growth, meta.damage);
+    if let Some(entity) = world.tile_entity(&pos) {
+        output.push_str(&format!("\n{}", entity));
+    }
+    Ok(output)
+}
+
+fn meta(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let pos = tile_arg(&world, args, 0)?;
+    let mut meta = world.meta(&pos);
+    match args.get(2) {
+        Some("orientation") => meta.orientation = args.parse::<Orientation>(3, "orientation")?,
+        Some("growth") => {
+            let growth = args.parse::<u8>(3, "growth")?;
+            if growth > MAX_GROWTH {
+                return Err(CommandError::Failed(format!("growth goes up to {}", MAX_GROWTH)));
+            }
+            meta.growth = growth;
+        }
+        Some("damage") => meta.damage = args.parse(3, "damage")?,
+        _ => return Err(args.usage_error()),
+    }
+    world.set_meta(&pos, meta);
+    Ok(format!("set {} of {} to {}", args.get(2).unwrap(), pos, args.get(3).unwrap()))
+}
+
+fn sign(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let pos = tile_arg(&world, args, 0)?;
+    let text: Vec<&str> = (2..args.len()).filter_map(|i| args.get(i)).collect();
+    match world.tile_entity_mut(&pos) {
+        Some(TileEntity::Sign(sign)) => {
+            sign.set_text(&text.join(" "));
+            Ok(format!("the sign at {} says '{}'", pos, sign.text))
+        }
+        _ => Err(CommandError::Failed(format!("there is no sign at {}", pos))),
+    }
+}
+
+fn put(ctx: &mut CommandContext, args: &Arguments) -> CommandResult {
+    let mut world = ctx.world.borrow_mut();
+    let pos = tile_arg(&world, args, 0)?;
+    let id = args.get(2).ok_or_else(|| args.usage_error())?.to_string();
+    let count = args.parse_opt::<u32>(3, "count")?.unwrap_or(1);
+    if count == 0 || count > MAX_STACK {
+        return Err(CommandError::Failed(format!("count has to be from 1 to {}", MAX_STACK)));
+    }
+    let entity = world.tile_entity_mut(&pos).ok_or_else(|| CommandError::Failed(format!("there is nothing to put items into at {}", pos)))?;
+    match entity.insert(ItemStack { id, count }) {
+        None => Ok(format!("put {} {} into the {}", count, args.get(2).unwrap(), entity.kind())),
+        Some(left) if left.count == count => Err(CommandError::Failed(format!("the {} has no room for {}", entity.kind(), left.id))),
+        Some(left) => Ok(format!("put {} into the {}, {} did not fit", count - left.count, entity.kind(), left.count)),
+    }
+}
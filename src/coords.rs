@@ -0,0 +1,157 @@
+use std::ops::Range;
+use crate::{EntityPosition, TilePosition, TILE_SIZE};
+
+// The game uses three coordinate spaces:
+// - world pixels: f32, origin at the bottom left of the world, y going up
+// - tiles: usize, tile (x, y) covers world pixels [x * TILE_SIZE, (x + 1) * TILE_SIZE) on both axes
+// - screen pixels: f32, origin at the top left of the window, y going down
+// Every conversion between them lives here.
+
+/// The tile containing a world position. Positions left of or below the world map to the first tile.
+pub fn world_to_tile(pos: &EntityPosition) -> TilePosition {
+    TilePosition((pos.0 / TILE_SIZE).floor() as usize, (pos.1 / TILE_SIZE).floor() as usize)
+}
+
+/// The bottom left corner of a tile in world pixels
+pub fn tile_to_world(pos: &TilePosition) -> EntityPosition {
+    EntityPosition(pos.0 as f32 * TILE_SIZE, pos.1 as f32 * TILE_SIZE)
+}
+
+/// The center of a tile in world pixels
+pub fn tile_center(pos: &TilePosition) -> EntityPosition {
+    tile_to_world(pos).offset((TILE_SIZE / 2.0, TILE_SIZE / 2.0))
+}
+
+/// Maps between world and screen pixels for one frame
+#[derive(Clone, Debug)]
+pub struct ViewTransform {
+    zoom: f32,
+    /// World x at the left edge of the screen
+    left: f32,
+    /// World y at the top edge of the screen
+    top: f32,
+    /// Size of the screen in pixels
+    viewport: (f32, f32),
+}
+
+impl ViewTransform {
+    pub fn new(zoom: f32, left: f32, top: f32, viewport: (f32, f32)) -> ViewTransform {
+        ViewTransform {
+            zoom,
+            left,
+            top,
+            viewport,
+        }
+    }
+
+    pub fn world_to_screen(&self, pos: &EntityPosition) -> (f32, f32) {
+        ((pos.0 - self.left) * self.zoom, (self.top - pos.1) * self.zoom)
+    }
+
+    pub fn screen_to_world(&self, screen: (f32, f32)) -> EntityPosition {
+        EntityPosition(self.left + screen.0 / self.zoom, self.top - screen.1 / self.zoom)
+    }
+
+    pub fn screen_to_tile(&self, screen: (f32, f32)) -> TilePosition {
+        world_to_tile(&self.screen_to_world(screen))
+    }
+
+    /// Screen rect (x, y, width, height) of a world rect whose bottom left corner is `pos`
+    pub fn world_rect_to_screen(&self, pos: &EntityPosition, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let (x, y) = self.world_to_screen(&pos.offset((0.0, height)));
+        (x, y, width * self.zoom, height * self.zoom)
+    }
+
+    /// Screen rect (x, y, width, height) covered by a tile
+    pub fn tile_to_screen(&self, pos: &TilePosition) -> (f32, f32, f32, f32) {
+        self.world_rect_to_screen(&tile_to_world(pos), TILE_SIZE, TILE_SIZE)
+    }
+
+    /// The columns and rows of tiles that are at least partly on screen, limited to the world size
+    pub fn visible_tiles(&self, world_tiles: (usize, usize)) -> (Range<usize>, Range<usize>) {
+        let right = self.left + self.viewport.0 / self.zoom;
+        let bottom = self.top - self.viewport.1 / self.zoom;
+        let range = |from: f32, to: f32, max: usize| {
+            let start = (from / TILE_SIZE).floor().max(0.0) as usize;
+            let end = ((to / TILE_SIZE).ceil().max(0.0) as usize).min(max);
+            start.min(end)..end
+        };
+        (range(self.left, right, world_tiles.0), range(bottom, self.top, world_tiles.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use super::*;
+
+    const ZOOMS: [f32; 7] = [0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 3.0];
+    const CASES: usize = 500;
+
+    /// Tiles on the edges of a world of `size` tiles, then random ones in between
+    fn tiles(rng: &mut StdRng, size: (usize, usize)) -> Vec<TilePosition> {
+        let mut tiles = vec![
+            TilePosition(0, 0),
+            TilePosition(size.0 - 1, 0),
+            TilePosition(0, size.1 - 1),
+            TilePosition(size.0 - 1, size.1 - 1),
+        ];
+        tiles.extend((0..CASES).map(|_| TilePosition(rng.gen_range(0..size.0), rng.gen_range(0..size.1))));
+        tiles
+    }
+
+    fn view(rng: &mut StdRng, zoom: f32) -> ViewTransform {
+        // Views of worlds smaller than the screen are centered, so their edges can be negative
+        let left = rng.gen_range(-500.0..5000.0);
+        let top = rng.gen_range(-500.0..5000.0);
+        ViewTransform::new(zoom, left, top, (800.0, 600.0))
+    }
+
+    #[test]
+    fn tile_to_world_round_trips() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for size in [(1, 1), (64, 20), (4096, 1024)] {
+            for tile in tiles(&mut rng, size) {
+                assert_eq!(world_to_tile(&tile_to_world(&tile)), tile);
+                assert_eq!(world_to_tile(&tile_center(&tile)), tile);
+            }
+        }
+    }
+
+    #[test]
+    fn world_to_screen_round_trips() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for zoom in ZOOMS {
+            for _ in 0..CASES {
+                let view = view(&mut rng, zoom);
+                let pos = EntityPosition(rng.gen_range(0.0..20000.0), rng.gen_range(0.0..20000.0));
+                let back = view.screen_to_world(view.world_to_screen(&pos));
+                assert!((back.0 - pos.0).abs() < 0.01 && (back.1 - pos.1).abs() < 0.01,
+                        "{} came back as {} at zoom {}", pos, back, zoom);
+            }
+        }
+    }
+
+    #[test]
+    fn tile_to_screen_round_trips() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for zoom in ZOOMS {
+            let view = view(&mut rng, zoom);
+            for tile in tiles(&mut rng, (4096, 1024)) {
+                // The center of the tile's screen rect, away from the edges it shares with its neighbours
+                let (x, y, w, h) = view.tile_to_screen(&tile);
+                assert_eq!(view.screen_to_tile((x + w / 2.0, y + h / 2.0)), tile, "at zoom {}", zoom);
+            }
+        }
+    }
+
+    #[test]
+    fn tile_rect_matches_zoom() {
+        for zoom in ZOOMS {
+            let view = ViewTransform::new(zoom, 0.0, 0.0, (800.0, 600.0));
+            let (_, _, w, h) = view.tile_to_screen(&TilePosition(3, 7));
+            assert_eq!((w, h), (TILE_SIZE * zoom, TILE_SIZE * zoom));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, PathBuilder, Point, SolidSource, Source, StrokeStyle};
+use crate::ai::Brain;
+use crate::coords::ViewTransform;
+use crate::ecs::component::{Collider, Position, Velocity};
+use crate::ecs::EntityId;
+use crate::tile::{self, base::BaseTile};
+use crate::world::World;
+use crate::TilePosition;
+use crate::ui;
+
+/// The world is not split into chunks yet, this is the size the chunk border layer marks out
+pub const CHUNK_SIZE: usize = 16;
+
+/// Counts frames and ticks to report rates once a second
+pub struct FrameStats {
+    frames: u32,
+    ticks: u32,
+    tick_time: Duration,
+    window_start: Instant,
+    pub fps: f32,
+    pub tps: f32,
+    /// Average time a world update took over the last second
+    pub tick_ms: f32,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            frames: 0,
+            ticks: 0,
+            tick_time: Duration::ZERO,
+            window_start: Instant::now(),
+            fps: 0.0,
+            tps: 0.0,
+            tick_ms: 0.0,
+        }
+    }
+
+    pub fn frame(&mut self) {
+        self.frames += 1;
+        self.roll();
+    }
+
+    /// Records a world update that took `duration`
+    pub fn tick(&mut self, duration: Duration) {
+        self.ticks += 1;
+        self.tick_time += duration;
+        self.roll();
+    }
+
+    fn roll(&mut self) {
+        let elapsed = self.window_start.elapsed().as_secs_f32();
+        if elapsed < 1.0 {
+            return;
+        }
+        self.fps = self.frames as f32 / elapsed;
+        self.tps = self.ticks as f32 / elapsed;
+        self.tick_ms = if self.ticks == 0 { 0.0 } else { self.tick_time.as_secs_f32() * 1000.0 / self.ticks as f32 };
+        self.frames = 0;
+        self.ticks = 0;
+        self.tick_time = Duration::ZERO;
+        self.window_start = Instant::now();
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        FrameStats::new()
+    }
+}
+
+/// Extra world space drawings of the debug overlay, each toggled on its own
+#[derive(Default)]
+pub struct DebugLayers {
+    pub grid: bool,
+    pub colliders: bool,
+    pub chunks: bool,
+    pub light: bool,
+}
+
+/// The F3 overlay. Its text goes to the HUD debug panel, its layers are drawn over the world.
+#[derive(Default)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    pub layers: DebugLayers,
+}
+
+impl DebugOverlay {
+    /// The lines shown in the debug panel
+    pub fn lines(&self, world: &World, player: EntityId, stats: &FrameStats, cursor: Option<TilePosition>) -> Vec<String> {
+        let mut lines = vec![
+            format!("fps={:.0} tps={:.0} tick={:.2}ms", stats.fps, stats.tps, stats.tick_ms),
+            format!("entities={} particles={}", world.ecs().entities().len(), world.particles().len()),
+        ];
+        let ecs = world.ecs();
+        if let (Some(pos), Some(vel)) = (ecs.get::<Position>(player), ecs.get::<Velocity>(player)) {
+            lines.push(format!("pos={} vel={}", pos.0, vel.0));
+            lines.push(format!("tile_pos={}", pos.0.to_tile_coords()));
+        }
+        if let Some(cursor) = cursor {
+            let tile = world.get_tile(&cursor).map_or("none".to_string(), |tile| format!("{:?}", tile.get_type()));
+            let name = world.get_tile(&cursor).and_then(|t| tile::name_of(t.as_ref())).unwrap_or_else(|| "-".to_string());
+            let connections = world.get_tile(&cursor)
+                .and_then(|t| t.as_any().downcast_ref::<BaseTile>())
+                .map_or(0, |t| t.connections().0);
+            lines.push(format!("cursor={} {} {} mask={} light={}", cursor, tile, name, connections, world.light_at(&cursor)));
+            let meta = world.meta(&cursor);
+            if !meta.is_default() {
+                lines.push(format!("orientation={} growth={} damage={}", meta.orientation, meta.growth, meta.damage));
+            }
+            if let Some(entity) = world.tile_entity(&cursor) {
+                lines.push(entity.to_string());
+            }
+            if let Some(def) = world.get_def(&cursor) {
+                lines.push(format!("hardness={} friction={} drops=[{}]", def.hardness, def.friction, def.drops.join(", ")));
+            }
+        }
+        lines.push(format!("time={} day={}", world.clock().time(), world.clock().day()));
+        lines.push(format!("layers: grid[F4]={} boxes[F5]={} chunks[F6]={} light[F7]={}",
+                           on_off(self.layers.grid), on_off(self.layers.colliders), on_off(self.layers.chunks), on_off(self.layers.light)));
+        lines
+    }
+
+    /// Draws the enabled layers over the world
+    pub fn render_layers(&self, target: &mut DrawTarget, font: &Font, world: &World, view: &ViewTransform) {
+        if !self.visible {
+            return;
+        }
+        let (columns, rows) = view.visible_tiles((world.width(), world.height()));
+        if self.layers.grid {
+            let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0x50, 0, 0, 0));
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    let (sx, sy, w, h) = view.tile_to_screen(&TilePosition(x, y));
+                    stroke_rect(target, (sx, sy, w, h), &source, 1.0);
+                }
+            }
+        }
+        if self.layers.chunks {
+            let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0xc0, 0xff, 0x40, 0xff));
+            for x in (0..world.width()).step_by(CHUNK_SIZE) {
+                let (sx, _, _, _) = view.tile_to_screen(&TilePosition(x, 0));
+                target.fill_rect(sx - 1.0, 0.0, 2.0, target.height() as f32, &source, &DrawOptions::new());
+            }
+            for y in (0..world.height()).step_by(CHUNK_SIZE) {
+                let (_, sy, _, h) = view.tile_to_screen(&TilePosition(0, y));
+                target.fill_rect(0.0, sy + h - 1.0, target.width() as f32, 2.0, &source, &DrawOptions::new());
+            }
+        }
+        if self.layers.light {
+            let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0xff, 0x60));
+            for y in rows {
+                for x in columns.clone() {
+                    let pos = TilePosition(x, y);
+                    let (sx, sy, _, h) = view.tile_to_screen(&pos);
+                    ui::draw_text(target, font, 9., &world.light_at(&pos).to_string(), Point::new(sx + 2.0, sy + h - 3.0), &source, &DrawOptions::new());
+                }
+            }
+        }
+        if self.layers.colliders {
+            let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0x20, 0xff, 0x20));
+            let positions = world.ecs().storage::<Position>();
+            let brains = world.ecs().storage::<Brain>();
+            for (id, collider) in world.ecs().storage::<Collider>().iter() {
+                if let Some(Position(pos)) = positions.get(id) {
+                    let rect = view.world_rect_to_screen(pos, collider.width, collider.height);
+                    stroke_rect(target, rect, &source, 1.0);
+                    // What mobs are up to, above their box
+                    if let Some(brain) = brains.get(id) {
+                        ui::draw_text(target, font, 9., &format!("{:?}", brain.state()), Point::new(rect.0, rect.1 - 2.0), &source, &DrawOptions::new());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn stroke_rect(target: &mut DrawTarget, (x, y, w, h): (f32, f32, f32, f32), source: &Source, width: f32) {
+    let mut pb = PathBuilder::new();
+    pb.rect(x, y, w, h);
+    let style = StrokeStyle { width, ..StrokeStyle::default() };
+    target.stroke(&pb.finish(), source, &style, &DrawOptions::new());
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
@@ -0,0 +1,184 @@
+pub mod component;
+pub mod system;
+
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use crate::ai::Brain;
+use crate::ecs::component::{Animator, Collider, ContactDamage, Controller, Facing, FloatingText, Health, Melee, Position, Projectile, Renderable, ScriptBehaviour, Velocity};
+use crate::entity::EntityType;
+
+/// Handle to an entity living in an [`Ecs`]. Ids are never reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId(usize);
+
+impl Display for EntityId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Storage for a single component type, ordered by entity id so iteration is deterministic
+pub struct Storage<T> {
+    items: BTreeMap<EntityId, T>
+}
+
+impl<T> Storage<T> {
+    fn new() -> Storage<T> {
+        Storage {
+            items: BTreeMap::new()
+        }
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        self.items.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        self.items.get_mut(&id)
+    }
+
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.items.contains_key(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.items.iter().map(|(id, c)| (*id, c))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        self.items.iter_mut().map(|(id, c)| (*id, c))
+    }
+}
+
+/// Type-erased view of a storage, so an entity can be removed from every storage at once
+trait AnyStorage {
+    fn remove(&self, id: EntityId);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: 'static> AnyStorage for RefCell<Storage<T>> {
+    fn remove(&self, id: EntityId) {
+        self.borrow_mut().items.remove(&id);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// A group of components that are spawned together, e.g. everything that makes up a player
+pub trait Bundle {
+    fn insert_into(self, ecs: &mut Ecs, id: EntityId);
+}
+
+pub struct Ecs {
+    next_id: usize,
+    alive: Vec<EntityId>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>
+}
+
+impl Ecs {
+    pub fn new() -> Ecs {
+        let mut ecs = Ecs {
+            next_id: 0,
+            alive: vec![],
+            storages: HashMap::new(),
+        };
+        ecs.register::<Position>();
+        ecs.register::<Velocity>();
+        ecs.register::<Collider>();
+        ecs.register::<Health>();
+        ecs.register::<Renderable>();
+        ecs.register::<Controller>();
+        ecs.register::<EntityType>();
+        ecs.register::<Brain>();
+        ecs.register::<ContactDamage>();
+        ecs.register::<Projectile>();
+        ecs.register::<Facing>();
+        ecs.register::<Melee>();
+        ecs.register::<FloatingText>();
+        ecs.register::<Animator>();
+        ecs.register::<ScriptBehaviour>();
+        ecs
+    }
+
+    /// Registers a component type. Registering the same type twice does nothing.
+    pub fn register<T: 'static>(&mut self) {
+        self.storages.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(RefCell::new(Storage::<T>::new())));
+    }
+
+    pub fn spawn(&mut self) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        self.alive.push(id);
+        id
+    }
+
+    pub fn spawn_bundle(&mut self, bundle: impl Bundle) -> EntityId {
+        let id = self.spawn();
+        bundle.insert_into(self, id);
+        id
+    }
+
+    /// Removes the entity and all of its components
+    pub fn despawn(&mut self, id: EntityId) {
+        if let Some(i) = self.alive.iter().position(|e| *e == id) {
+            self.alive.remove(i);
+            for storage in self.storages.values() {
+                storage.remove(id);
+            }
+        }
+    }
+
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        self.alive.contains(&id)
+    }
+
+    pub fn entities(&self) -> &[EntityId] {
+        &self.alive
+    }
+
+    pub fn insert<T: 'static>(&mut self, id: EntityId, component: T) {
+        self.register::<T>();
+        self.storage_mut::<T>().items.insert(id, component);
+    }
+
+    pub fn has<T: 'static>(&self, id: EntityId) -> bool {
+        self.storage::<T>().contains(id)
+    }
+
+    pub fn get<T: 'static>(&self, id: EntityId) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>(), |s| s.get(id)).ok()
+    }
+
+    pub fn get_mut<T: 'static>(&self, id: EntityId) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.storage_mut::<T>(), |s| s.get_mut(id)).ok()
+    }
+
+    /// Borrows the storage of a component type. Panics if the type was never registered.
+    pub fn storage<T: 'static>(&self) -> Ref<'_, Storage<T>> {
+        self.cell::<T>().borrow()
+    }
+
+    /// Mutably borrows the storage of a component type. Panics if the type was never registered.
+    pub fn storage_mut<T: 'static>(&self) -> RefMut<'_, Storage<T>> {
+        self.cell::<T>().borrow_mut()
+    }
+
+    fn cell<T: 'static>(&self) -> &RefCell<Storage<T>> {
+        self.storages.get(&TypeId::of::<T>())
+            .unwrap_or_else(|| panic!("component {} is not registered", std::any::type_name::<T>()))
+            .as_any()
+            .downcast_ref::<RefCell<Storage<T>>>()
+            .unwrap()
+    }
+}
+
+impl Default for Ecs {
+    fn default() -> Self {
+        Ecs::new()
+    }
+}
@@ -0,0 +1,203 @@
+use raqote::Color;
+use strum_macros::Display;
+use crate::ecs::EntityId;
+use crate::FloatVector2D;
+
+/// Bottom left corner of the entity in world pixels, see [`crate::coords`]
+#[derive(Clone, Debug)]
+pub struct Position(pub FloatVector2D);
+
+#[derive(Clone, Debug)]
+pub struct Velocity(pub FloatVector2D);
+
+/// Axis aligned box used for collision, extending right and up from the position
+#[derive(Clone, Debug)]
+pub struct Collider {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Collider {
+    /// Whether this collider at `pos` overlaps `other` at `other_pos`
+    pub fn overlaps(&self, pos: &FloatVector2D, other: &Collider, other_pos: &FloatVector2D) -> bool {
+        pos.0 < other_pos.0 + other.width && other_pos.0 < pos.0 + self.width
+            && pos.1 < other_pos.1 + other.height && other_pos.1 < pos.1 + self.height
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Health {
+    pub current: u8,
+    pub max: u8,
+    /// Ticks the entity ignores damage for after being hit
+    pub invulnerability: u32,
+    pub invulnerable_ticks: u32,
+    /// Ticks between regenerating one health point, 0 to never regenerate
+    pub regen_interval: u32,
+    pub regen_ticks: u32,
+    /// Damage taken this tick, applied by the damage system
+    pending: u8,
+}
+
+impl Health {
+    pub fn new(max: u8) -> Health {
+        Health {
+            current: max,
+            max,
+            invulnerability: 0,
+            invulnerable_ticks: 0,
+            regen_interval: 0,
+            regen_ticks: 0,
+            pending: 0
+        }
+    }
+
+    pub fn with_invulnerability(mut self, ticks: u32) -> Health {
+        self.invulnerability = ticks;
+        self
+    }
+
+    pub fn with_regen(mut self, interval: u32) -> Health {
+        self.regen_interval = interval;
+        self
+    }
+
+    /// Queues damage for the damage system. Ignored while invulnerable.
+    pub fn take_damage(&mut self, damage: u8) {
+        if self.is_invulnerable() {
+            return;
+        }
+        self.pending = self.pending.saturating_add(damage);
+    }
+
+    pub fn take_pending(&mut self) -> u8 {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_ticks > 0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Renderable {
+    pub color: Color,
+    pub width: f32,
+    pub height: f32,
+    /// Draws the current health above the entity
+    pub show_health: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facing {
+    Left,
+    Right,
+}
+
+impl Facing {
+    pub fn sign(&self) -> f32 {
+        match self {
+            Facing::Left => -1.0,
+            Facing::Right => 1.0,
+        }
+    }
+}
+
+/// Which animation of an entity is playing, used as the state part of its atlas key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum AnimationState {
+    Idle,
+    Walk,
+    Jump,
+    Fall,
+}
+
+/// Picks the animation an entity plays from how it moves
+#[derive(Clone, Debug)]
+pub struct Animator {
+    pub state: AnimationState,
+    /// World tick the current state started on, so each animation starts from its first frame
+    pub since: u64,
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator { state: AnimationState::Idle, since: 0 }
+    }
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Animator::new()
+    }
+}
+
+/// Runs the script behaviour registered under this name every tick
+#[derive(Clone, Debug)]
+pub struct ScriptBehaviour(pub String);
+
+/// Marks an entity as driven by keyboard input
+#[derive(Clone, Debug)]
+pub struct Controller {
+    pub move_speed: f32,
+}
+
+/// Hurts controlled entities that touch this one
+#[derive(Clone, Debug)]
+pub struct ContactDamage {
+    pub damage: u8,
+    /// Ticks to wait between hits
+    pub cooldown: u32,
+    pub ticks_left: u32,
+}
+
+impl ContactDamage {
+    pub fn new(damage: u8, cooldown: u32) -> ContactDamage {
+        ContactDamage {
+            damage,
+            cooldown,
+            ticks_left: 0
+        }
+    }
+}
+
+/// Flies ballistically instead of using walking physics, hurting whatever it hits
+#[derive(Clone, Debug)]
+pub struct Projectile {
+    pub damage: u8,
+    /// Speed given to the entity that is hit, in the direction of travel
+    pub knockback: f32,
+    /// Shatters fragile tiles instead of stopping at them
+    pub breaks_fragile: bool,
+    /// The entity that fired it, which it never hits
+    pub owner: Option<EntityId>,
+    /// Ticks until the projectile disappears
+    pub ticks_left: u32,
+    /// Set once the projectile has hit a tile and stopped
+    pub stuck: bool,
+}
+
+/// A close range attack in the facing direction
+#[derive(Clone, Debug)]
+pub struct Melee {
+    pub damage: u8,
+    /// How far in front of the attacker the hitbox reaches, in pixels
+    pub reach: f32,
+    pub knockback: f32,
+    /// Ticks to wait between attacks
+    pub cooldown: u32,
+    pub ticks_left: u32,
+}
+
+/// Text drifting upwards that disappears after a while, such as damage numbers
+#[derive(Clone, Debug)]
+pub struct FloatingText {
+    pub text: String,
+    pub color: Color,
+    pub ticks_left: u32,
+}
@@ -0,0 +1,11 @@
+pub mod physics;
+pub mod damage;
+pub mod render;
+pub mod input;
+pub mod ai;
+pub mod contact;
+pub mod collision;
+pub mod projectile;
+pub mod melee;
+pub mod floating_text;
+pub mod animation;
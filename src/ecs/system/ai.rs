@@ -0,0 +1,34 @@
+use crate::ai::{distance, Brain, Senses};
+use crate::ecs::component::{Collider, Controller, Position, Velocity};
+use crate::ecs::system::physics;
+use crate::ecs::Ecs;
+use crate::world::World;
+
+/// Lets every mob with a brain pick a behaviour and steer towards it
+pub fn run(ecs: &Ecs, world: &World) {
+    let positions = ecs.storage::<Position>();
+    let colliders = ecs.storage::<Collider>();
+    let players: Vec<_> = ecs.storage::<Controller>().iter()
+        .filter_map(|(id, _)| positions.get(id).map(|Position(pos)| (id, pos.clone())))
+        .collect();
+
+    let mut velocities = ecs.storage_mut::<Velocity>();
+    let mut rng = world.rng();
+    for (id, brain) in ecs.storage_mut::<Brain>().iter_mut() {
+        let (Some(Position(pos)), Some(Velocity(vel))) = (positions.get(id), velocities.get_mut(id)) else {
+            continue;
+        };
+        let target = players.iter()
+            .min_by(|a, b| distance(pos, &a.1).total_cmp(&distance(pos, &b.1)))
+            .cloned();
+        let senses = Senses {
+            pos: pos.clone(),
+            target,
+            grounded: vel.1 <= 0.0 && physics::is_grounded(world, pos, colliders.get(id)),
+        };
+        brain.think(&senses, &mut *rng);
+        let steering = brain.steer(&senses);
+        vel.0 += steering.push.0;
+        vel.1 += steering.push.1;
+    }
+}
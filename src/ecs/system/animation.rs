@@ -0,0 +1,42 @@
+use crate::ecs::component::{AnimationState, Animator, Collider, Position, Velocity};
+use crate::ecs::Ecs;
+use crate::ecs::system::physics;
+use crate::world::World;
+
+/// Horizontal speed below which a grounded entity counts as standing still
+const WALK_THRESHOLD: f32 = 0.1;
+/// Falls shorter than this many ticks land without kicking up dust
+const LANDING_TICKS: u64 = 6;
+
+/// Switches each animated entity to the animation matching its movement, puffing dust when it lands
+pub fn run(ecs: &Ecs, world: &World) {
+    let positions = ecs.storage::<Position>();
+    let velocities = ecs.storage::<Velocity>();
+    let colliders = ecs.storage::<Collider>();
+    for (id, animator) in ecs.storage_mut::<Animator>().iter_mut() {
+        let (Some(Position(pos)), Some(Velocity(vel))) = (positions.get(id), velocities.get(id)) else {
+            continue;
+        };
+        let grounded = vel.1 <= 0.0 && physics::is_grounded(world, pos, colliders.get(id));
+        let state = if grounded && vel.0.abs() > WALK_THRESHOLD {
+            AnimationState::Walk
+        } else if grounded {
+            AnimationState::Idle
+        } else if vel.1 > 0.0 {
+            AnimationState::Jump
+        } else {
+            AnimationState::Fall
+        };
+        if grounded && animator.state == AnimationState::Fall && world.tick() - animator.since >= LANDING_TICKS {
+            let width = colliders.get(id).map_or(0.0, |c| c.width);
+            let ground = world.get_def(&pos.offset((width / 2.0, -1.0)).to_tile_coords());
+            if let Some(ground) = ground {
+                world.particles().dust(pos, width, ground.color(), &mut *world.rng());
+            }
+        }
+        if state != animator.state {
+            animator.state = state;
+            animator.since = world.tick();
+        }
+    }
+}
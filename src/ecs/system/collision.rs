@@ -0,0 +1,23 @@
+use crate::ecs::component::{Collider, Position};
+use crate::ecs::{Ecs, EntityId};
+use crate::FloatVector2D;
+
+/// Entities whose colliders overlap the collider of `id`, in id order
+pub fn overlapping(ecs: &Ecs, id: EntityId) -> Vec<EntityId> {
+    let (Some(pos), Some(collider)) = (ecs.get::<Position>(id).map(|p| p.0.clone()), ecs.get::<Collider>(id).map(|c| c.clone())) else {
+        return vec![];
+    };
+    in_box(ecs, &pos, &collider).into_iter()
+        .filter(|other| *other != id)
+        .collect()
+}
+
+/// Entities whose colliders overlap a box with its bottom left corner at `pos`, in id order
+pub fn in_box(ecs: &Ecs, pos: &FloatVector2D, area: &Collider) -> Vec<EntityId> {
+    let positions = ecs.storage::<Position>();
+    ecs.storage::<Collider>().iter()
+        .filter(|(other, other_collider)| positions.get(*other)
+            .is_some_and(|Position(other_pos)| area.overlaps(pos, other_collider, other_pos)))
+        .map(|(other, _)| other)
+        .collect()
+}
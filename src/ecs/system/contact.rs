@@ -0,0 +1,24 @@
+use crate::ecs::component::{ContactDamage, Controller, Health};
+use crate::ecs::system::collision;
+use crate::ecs::Ecs;
+
+/// Damages controlled entities touching anything with contact damage
+pub fn run(ecs: &Ecs) {
+    let controllers = ecs.storage::<Controller>();
+    let mut healths = ecs.storage_mut::<Health>();
+    for (id, contact) in ecs.storage_mut::<ContactDamage>().iter_mut() {
+        if contact.ticks_left > 0 {
+            contact.ticks_left -= 1;
+            continue;
+        }
+        for other in collision::overlapping(ecs, id) {
+            if !controllers.contains(other) {
+                continue;
+            }
+            if let Some(health) = healths.get_mut(other) {
+                health.take_damage(contact.damage);
+                contact.ticks_left = contact.cooldown;
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use raqote::Color;
+use crate::ecs::component::{Collider, Controller, FloatingText, Health, Position, Velocity};
+use crate::ecs::{Ecs, EntityId};
+use crate::FloatVector2D;
+
+/// Ticks a damage number stays on screen
+const DAMAGE_NUMBER_TICKS: u32 = 25;
+
+/// Regenerates health and applies damage taken this tick, leaving a damage number above the
+/// target. Dead controlled entities respawn, anything else is despawned.
+pub fn run(ecs: &mut Ecs) {
+    let mut hurt = vec![];
+    let mut dead = vec![];
+    for (id, health) in ecs.storage_mut::<Health>().iter_mut() {
+        health.invulnerable_ticks = health.invulnerable_ticks.saturating_sub(1);
+        if health.regen_interval > 0 && health.current < health.max {
+            health.regen_ticks += 1;
+            if health.regen_ticks >= health.regen_interval {
+                health.regen_ticks = 0;
+                health.current += 1;
+            }
+        }
+
+        let damage = health.take_pending();
+        if damage == 0 {
+            continue;
+        }
+        health.current = health.current.saturating_sub(damage);
+        health.invulnerable_ticks = health.invulnerability;
+        health.regen_ticks = 0;
+        hurt.push((id, damage));
+        if !health.is_alive() {
+            dead.push(id);
+        }
+    }
+
+    for (id, damage) in hurt {
+        let Some(pos) = ecs.get::<Position>(id).map(|p| p.0.clone()) else {
+            continue;
+        };
+        let height = ecs.get::<Collider>(id).map_or(0.0, |c| c.height);
+        let pos = pos.offset((0.0, height + 4.0));
+        let number = ecs.spawn();
+        ecs.insert(number, Position(pos));
+        ecs.insert(number, FloatingText {
+            text: format!("-{}", damage),
+            color: Color::new(0xff, 0xe0, 0x20, 0x20),
+            ticks_left: DAMAGE_NUMBER_TICKS,
+        });
+    }
+
+    for id in dead {
+        if ecs.has::<Controller>(id) {
+            respawn(ecs, id);
+        } else {
+            ecs.despawn(id);
+        }
+    }
+}
+
+fn respawn(ecs: &Ecs, id: EntityId) {
+    if let Some(mut health) = ecs.get_mut::<Health>(id) {
+        health.current = health.max;
+    }
+    if let Some(mut vel) = ecs.get_mut::<Velocity>(id) {
+        vel.0 = FloatVector2D(0.0, 0.0);
+    }
+}
@@ -0,0 +1,21 @@
+use crate::ecs::component::{FloatingText, Position};
+use crate::ecs::Ecs;
+
+/// Drifts floating text upwards and removes it once it has faded
+pub fn run(ecs: &mut Ecs) {
+    let mut expired = vec![];
+    {
+        let mut positions = ecs.storage_mut::<Position>();
+        for (id, text) in ecs.storage_mut::<FloatingText>().iter_mut() {
+            text.ticks_left = text.ticks_left.saturating_sub(1);
+            if text.ticks_left == 0 {
+                expired.push(id);
+            } else if let Some(Position(pos)) = positions.get_mut(id) {
+                pos.1 += 1.0;
+            }
+        }
+    }
+    for id in expired {
+        ecs.despawn(id);
+    }
+}
@@ -0,0 +1,24 @@
+use minifb::Key;
+use crate::ecs::component::{Controller, Velocity};
+use crate::ecs::Ecs;
+
+/// Pushes every controlled entity in the direction of the pressed movement keys
+pub fn run(ecs: &Ecs, keys: &[Key]) {
+    let controllers = ecs.storage::<Controller>();
+    let mut velocities = ecs.storage_mut::<Velocity>();
+    for (id, controller) in controllers.iter() {
+        let Some(Velocity(vel)) = velocities.get_mut(id) else {
+            continue;
+        };
+        let speed = controller.move_speed;
+        for key in keys {
+            match key {
+                Key::W => vel.1 += speed,
+                Key::S => vel.1 -= speed,
+                Key::A => vel.0 -= speed,
+                Key::D => vel.0 += speed,
+                _ => (),
+            }
+        }
+    }
+}
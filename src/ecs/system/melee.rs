@@ -0,0 +1,49 @@
+use crate::ecs::component::{Collider, Facing, Health, Melee, Position, Velocity};
+use crate::ecs::system::collision;
+use crate::ecs::{Ecs, EntityId};
+use crate::FloatVector2D;
+
+/// Counts down attack cooldowns
+pub fn run(ecs: &Ecs) {
+    for (_, melee) in ecs.storage_mut::<Melee>().iter_mut() {
+        melee.ticks_left = melee.ticks_left.saturating_sub(1);
+    }
+}
+
+/// Swings at everything with health in front of the attacker, knocking it away.
+/// Returns false if the attack is still cooling down.
+pub fn attack(ecs: &Ecs, attacker: EntityId) -> bool {
+    let Some(mut melee) = ecs.get_mut::<Melee>(attacker) else {
+        return false;
+    };
+    if melee.ticks_left > 0 {
+        return false;
+    }
+    melee.ticks_left = melee.cooldown;
+
+    let (Some(Position(pos)), Some(collider)) = (ecs.get::<Position>(attacker).map(|p| p.clone()), ecs.get::<Collider>(attacker).map(|c| c.clone())) else {
+        return true;
+    };
+    let facing = ecs.get::<Facing>(attacker).map_or(Facing::Right, |f| *f);
+    let hitbox = Collider { width: melee.reach, height: collider.height };
+    let corner = match facing {
+        Facing::Left => pos.offset((-melee.reach, 0.0)),
+        Facing::Right => pos.offset((collider.width, 0.0)),
+    };
+    for target in collision::in_box(ecs, &corner, &hitbox) {
+        if target == attacker {
+            continue;
+        }
+        let Some(mut health) = ecs.get_mut::<Health>(target) else {
+            continue;
+        };
+        if health.is_invulnerable() {
+            continue;
+        }
+        health.take_damage(melee.damage);
+        if let Some(mut vel) = ecs.get_mut::<Velocity>(target) {
+            vel.0 = FloatVector2D(facing.sign() * melee.knockback, melee.knockback / 2.0);
+        }
+    }
+    true
+}
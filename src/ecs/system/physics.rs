@@ -0,0 +1,72 @@
+use crate::ecs::component::{Collider, Facing, Position, Projectile, Velocity};
+use crate::ecs::Ecs;
+use crate::EntityPosition;
+use crate::world::World;
+
+/// How much velocity is divided by each tick, on ground with a friction of 1
+const FRICTION_VALUE: f32 = 2.0;
+const GRAVITY_FACTOR: f32 = 5.0;
+
+/// Moves every entity with a velocity, stopping at solid tiles and applying friction and gravity.
+/// Projectiles are moved by the projectile system instead.
+pub fn run(ecs: &Ecs, world: &World) {
+    let colliders = ecs.storage::<Collider>();
+    let projectiles = ecs.storage::<Projectile>();
+    let mut positions = ecs.storage_mut::<Position>();
+    let mut velocities = ecs.storage_mut::<Velocity>();
+    let mut facings = ecs.storage_mut::<Facing>();
+    let bounds = world.pixel_size();
+    for (id, Velocity(vel)) in velocities.iter_mut() {
+        if projectiles.contains(id) {
+            continue;
+        }
+        if let Some(facing) = facings.get_mut(id) {
+            if vel.0 > 0.0 {
+                *facing = Facing::Right;
+            } else if vel.0 < 0.0 {
+                *facing = Facing::Left;
+            }
+        }
+        let Some(Position(pos)) = positions.get_mut(id) else {
+            continue;
+        };
+        let (width, height) = colliders.get(id).map_or((0.0, 0.0), |c| (c.width, c.height));
+
+        // Probe the pixel just outside the box in the direction of movement
+        if vel.0 > 0.0 {
+            if !world.is_occupied(&pos.offset((width, height / 2.0)).to_tile_coords()) {
+                pos.0 += 1.0;
+            }
+        } else if vel.0 < 0.0 && !world.is_occupied(&pos.offset((-1.0, height / 2.0)).to_tile_coords()) {
+            pos.0 -= 1.0;
+        }
+        if vel.1 > 0.0 {
+            if !world.is_occupied(&pos.offset((width / 2.0, height)).to_tile_coords()) {
+                pos.1 += 1.0;
+            }
+        } else if vel.1 < 0.0 && !world.is_occupied(&pos.offset((width / 2.0, -1.0)).to_tile_coords()) {
+            pos.1 -= 1.0;
+        }
+
+        pos.0 = pos.0.clamp(0.0, (bounds.0 - width).max(0.0));
+        pos.1 = pos.1.clamp(0.0, (bounds.1 - height).max(0.0));
+
+        // Slippery ground, such as ice, slows walking entities less
+        let ground = world.get_def(&pos.offset((width / 2.0, -1.0)).to_tile_coords()).map_or(1.0, |def| def.friction);
+        vel.0 /= 1.0 + (FRICTION_VALUE - 1.0) * ground;
+        if vel.0.abs() < 0.01 {
+            vel.0 = 0.0;
+        }
+        vel.1 /= FRICTION_VALUE;
+        if vel.1.abs() < 0.01 {
+            vel.1 = 0.0;
+            vel.1 -= GRAVITY_FACTOR;
+        }
+    }
+}
+
+/// Whether the tile right below a collider at `pos` is solid
+pub fn is_grounded(world: &World, pos: &EntityPosition, collider: Option<&Collider>) -> bool {
+    let width = collider.map_or(0.0, |c| c.width);
+    world.is_occupied(&pos.offset((width / 2.0, -1.0)).to_tile_coords())
+}
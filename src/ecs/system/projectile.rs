@@ -0,0 +1,91 @@
+use crate::ecs::component::{Health, Position, Projectile, Velocity};
+use crate::ecs::system::collision;
+use crate::ecs::{Ecs, EntityId};
+use crate::{FloatVector2D, TilePosition, TILE_SIZE};
+use crate::world::World;
+
+const GRAVITY: f32 = 0.5;
+/// Longest distance moved between collision checks, so fast projectiles can't skip tiles
+const MAX_STEP: f32 = TILE_SIZE / 4.0;
+/// Ticks a projectile stays stuck in a tile before disappearing
+const STUCK_TICKS: u32 = 60;
+
+/// What happened to projectiles this tick, applied by the world once systems are done
+#[derive(Default)]
+pub struct Impacts {
+    pub despawned: Vec<EntityId>,
+    pub broken_tiles: Vec<TilePosition>,
+}
+
+/// Moves projectiles under gravity, sticking them in tiles and damaging entities they hit
+pub fn run(ecs: &Ecs, world: &World) -> Impacts {
+    let mut impacts = Impacts::default();
+    let ids: Vec<EntityId> = ecs.storage::<Projectile>().iter().map(|(id, _)| id).collect();
+    for &id in ids.iter() {
+        let Some(mut projectile) = ecs.get_mut::<Projectile>(id) else {
+            continue;
+        };
+        projectile.ticks_left = projectile.ticks_left.saturating_sub(1);
+        if projectile.ticks_left == 0 {
+            impacts.despawned.push(id);
+            continue;
+        }
+        if projectile.stuck {
+            continue;
+        }
+
+        if fly(ecs, world, id, &mut projectile, &mut impacts) {
+            continue;
+        }
+        let hit = collision::overlapping(ecs, id).into_iter()
+            .find(|other| Some(*other) != projectile.owner && ecs.has::<Health>(*other) && !ids.contains(other));
+        if let Some(target) = hit {
+            strike(ecs, id, target, &projectile);
+            impacts.despawned.push(id);
+        }
+    }
+    impacts
+}
+
+/// Moves the projectile along its velocity in small steps. Returns true once it stopped at a tile.
+fn fly(ecs: &Ecs, world: &World, id: EntityId, projectile: &mut Projectile, impacts: &mut Impacts) -> bool {
+    let (Some(mut pos), Some(mut vel)) = (ecs.get_mut::<Position>(id), ecs.get_mut::<Velocity>(id)) else {
+        return false;
+    };
+    vel.0 .1 -= GRAVITY;
+    let bounds = world.pixel_size();
+    let steps = (vel.0 .0.abs().max(vel.0 .1.abs()) / MAX_STEP).ceil().max(1.0) as usize;
+    let step = (vel.0 .0 / steps as f32, vel.0 .1 / steps as f32);
+    for _ in 0..steps {
+        let next = pos.0.offset(step);
+        if next.0 < 0.0 || next.1 < 0.0 || next.0 > bounds.0 || next.1 > bounds.1 {
+            impacts.despawned.push(id);
+            return true;
+        }
+        let tile_pos = next.to_tile_coords();
+        if world.is_occupied(&tile_pos) {
+            if projectile.breaks_fragile && world.get_def(&tile_pos).is_some_and(|def| def.is_fragile()) {
+                impacts.broken_tiles.push(tile_pos);
+                impacts.despawned.push(id);
+            } else {
+                projectile.stuck = true;
+                projectile.ticks_left = STUCK_TICKS;
+                vel.0 = FloatVector2D(0.0, 0.0);
+            }
+            return true;
+        }
+        pos.0 = next;
+    }
+    false
+}
+
+/// Damages the target and knocks it back in the direction the projectile was flying
+fn strike(ecs: &Ecs, id: EntityId, target: EntityId, projectile: &Projectile) {
+    if let Some(mut health) = ecs.get_mut::<Health>(target) {
+        health.take_damage(projectile.damage);
+    }
+    let direction = ecs.get::<Velocity>(id).map_or(0.0, |v| v.0 .0.signum());
+    if let Some(mut vel) = ecs.get_mut::<Velocity>(target) {
+        vel.0 = FloatVector2D(direction * projectile.knockback, projectile.knockback / 2.0);
+    }
+}
@@ -0,0 +1,55 @@
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
+use crate::ecs::component::{AnimationState, Animator, Facing, FloatingText, Health, Position, Renderable};
+use crate::ecs::Ecs;
+use crate::coords::ViewTransform;
+use crate::entity::EntityType;
+use crate::sprite;
+use crate::ui;
+
+/// Draws every entity that has both a position and something to render
+pub fn run(ecs: &Ecs, target: &mut DrawTarget, font: &Font, view: &ViewTransform, tick: u64) {
+    let positions = ecs.storage::<Position>();
+    let renderables = ecs.storage::<Renderable>();
+    let healths = ecs.storage::<Health>();
+    let kinds = ecs.storage::<EntityType>();
+    let animators = ecs.storage::<Animator>();
+    let facings = ecs.storage::<Facing>();
+    for (id, renderable) in renderables.iter() {
+        let Some(Position(pos)) = positions.get(id) else {
+            continue;
+        };
+        let (x, y, width, height) = view.world_rect_to_screen(pos, renderable.width, renderable.height);
+        let source = Source::Solid(SolidSource::from(renderable.color));
+        let health = healths.get(id);
+        if renderable.show_health {
+            if let Some(health) = health {
+                ui::draw_text(target, font, 14., &format!("{} HP", health.current), Point::new(x - width / 2.0, y - 5.0),
+                                 &source, &DrawOptions::new());
+            }
+        }
+        // Blink while invulnerable
+        if health.is_some_and(|h| h.invulnerable_ticks % 4 >= 2) {
+            continue;
+        }
+        let (state, since) = animators.get(id).map_or((AnimationState::Idle, 0), |a| (a.state, a.since));
+        let animation = kinds.get(id).and_then(|kind| {
+            let atlas = sprite::atlas()?;
+            atlas.entity(*kind, &state.to_string()).or_else(|| atlas.entity(*kind, "idle"))
+        });
+        let flip = facings.get(id) == Some(&Facing::Left);
+        match animation {
+            Some(animation) => animation.frame_at(tick - since).draw(target, x, y, width, height, flip),
+            None => target.fill_rect(x, y, width, height, &source, &DrawOptions::new()),
+        }
+    }
+
+    for (id, text) in ecs.storage::<FloatingText>().iter() {
+        let Some(Position(pos)) = positions.get(id) else {
+            continue;
+        };
+        let (x, y) = view.world_to_screen(pos);
+        ui::draw_text(target, font, 13., &text.text, Point::new(x, y),
+                         &Source::Solid(SolidSource::from(text.color)), &DrawOptions::new());
+    }
+}
@@ -1,23 +1,61 @@
 pub mod player;
+pub mod slime;
+pub mod critter;
+pub mod projectile;
 
 use std::any::Any;
 use std::cell::RefCell;
 use std::rc::Weak;
+use std::str::FromStr;
 use font_kit::font::Font;
 use minifb::Window;
 use raqote::DrawTarget;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
+use crate::coords::ViewTransform;
+use crate::ecs::EntityId;
+use crate::entity::critter::CritterEntity;
+use crate::entity::player::PlayerEntity;
+use crate::entity::projectile::ProjectileEntity;
+use crate::entity::slime::SlimeEntity;
+use crate::plugin;
 use crate::{EntityPosition, TilePosition, Velocity};
 use crate::world::World;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
 pub enum EntityType {
-    Player
+    Player,
+    Slime,
+    Critter,
+    Arrow,
+    Rock,
+}
+
+/// Spawns an entity type by name, or one registered by a plugin such as "mymod:golem"
+pub fn spawn_named(world: &mut World, name: &str, pos: EntityPosition) -> Option<EntityId> {
+    match EntityType::from_str(name) {
+        Ok(kind) => Some(spawn(world, kind, pos)),
+        Err(_) => plugin::spawn_entity(world, name, pos),
+    }
+}
+
+/// Spawns the default bundle for an entity type
+pub fn spawn(world: &mut World, kind: EntityType, pos: EntityPosition) -> EntityId {
+    match kind {
+        EntityType::Player => world.spawn(PlayerEntity::new(Some(pos))),
+        EntityType::Slime => world.spawn(SlimeEntity::new(pos)),
+        EntityType::Critter => world.spawn(CritterEntity::new(pos)),
+        EntityType::Arrow => world.spawn(ProjectileEntity::arrow(pos, Velocity(0.0, 0.0), None)),
+        EntityType::Rock => world.spawn(ProjectileEntity::rock(pos, Velocity(0.0, 0.0), None)),
+    }
 }
 
 type Health = u8;
+
+/// Object-style entity API, kept as an adapter while entities migrate to the components in
+/// [`crate::ecs`]. World still updates and renders entities added through `World::add_entity`.
 pub trait Entity {
-    fn render(&self, target: &mut DrawTarget, font: &Font);
+    fn render(&self, target: &mut DrawTarget, font: &Font, view: &ViewTransform);
     fn update(&mut self, world: &World);
     fn get_type(&self) -> &EntityType;
     fn get_pos(&self) -> &EntityPosition;
@@ -0,0 +1,59 @@
+use raqote::Color;
+use crate::ai::{Brain, Gait, Temperament};
+use crate::ecs::{Bundle, Ecs, EntityId};
+use crate::ecs::component::{Animator, Collider, Facing, Health, Position, Renderable, Velocity};
+use crate::entity::EntityType;
+use crate::{EntityPosition, FloatVector2D};
+
+const CRITTER_WIDTH: f32 = 12.0;
+const CRITTER_HEIGHT: f32 = 10.0;
+const MAX_HEALTH: u8 = 10;
+const INVULNERABLE_TICKS: u32 = 8;
+const WALK_SPEED: f32 = 3.0;
+const SIGHT_RANGE: f32 = 60.0;
+
+/// Passive mob that wanders around and runs from the player
+pub struct CritterEntity {
+    pub position: Position,
+    pub velocity: Velocity,
+    pub collider: Collider,
+    pub health: Health,
+    pub facing: Facing,
+    pub animator: Animator,
+    pub renderable: Renderable,
+    pub brain: Brain,
+}
+
+impl CritterEntity {
+    pub fn new(pos: EntityPosition) -> CritterEntity {
+        CritterEntity {
+            position: Position(pos),
+            velocity: Velocity(FloatVector2D(0.0, 0.0)),
+            collider: Collider { width: CRITTER_WIDTH, height: CRITTER_HEIGHT },
+            health: Health::new(MAX_HEALTH).with_invulnerability(INVULNERABLE_TICKS),
+            facing: Facing::Right,
+            animator: Animator::new(),
+            renderable: Renderable {
+                color: Color::new(0xff, 0xc9, 0x8b, 0x5e),
+                width: CRITTER_WIDTH,
+                height: CRITTER_HEIGHT,
+                show_health: false,
+            },
+            brain: Brain::new(Temperament::Passive, Gait::Walk, WALK_SPEED, SIGHT_RANGE),
+        }
+    }
+}
+
+impl Bundle for CritterEntity {
+    fn insert_into(self, ecs: &mut Ecs, id: EntityId) {
+        ecs.insert(id, EntityType::Critter);
+        ecs.insert(id, self.position);
+        ecs.insert(id, self.velocity);
+        ecs.insert(id, self.collider);
+        ecs.insert(id, self.health);
+        ecs.insert(id, self.facing);
+        ecs.insert(id, self.animator);
+        ecs.insert(id, self.renderable);
+        ecs.insert(id, self.brain);
+    }
+}
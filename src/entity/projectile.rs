@@ -0,0 +1,71 @@
+use raqote::Color;
+use crate::ecs::{Bundle, Ecs, EntityId};
+use crate::ecs::component::{Collider, Position, Projectile, Renderable, Velocity};
+use crate::entity::EntityType;
+use crate::{EntityPosition, Velocity as Vector};
+
+/// Ticks a projectile may fly before it disappears
+const FLIGHT_TICKS: u32 = 150;
+
+/// A thrown or fired object, such as an arrow or a rock
+pub struct ProjectileEntity {
+    pub kind: EntityType,
+    pub position: Position,
+    pub velocity: Velocity,
+    pub collider: Collider,
+    pub renderable: Renderable,
+    pub projectile: Projectile,
+}
+
+impl ProjectileEntity {
+    /// Light and fast, sticks in whatever tile it hits
+    pub fn arrow(pos: EntityPosition, vel: Vector, owner: Option<EntityId>) -> ProjectileEntity {
+        ProjectileEntity::new(EntityType::Arrow, pos, vel, (8.0, 3.0), Color::new(0xff, 0x6b, 0x4a, 0x2b), Projectile {
+            damage: 8,
+            knockback: 6.0,
+            breaks_fragile: false,
+            owner,
+            ticks_left: FLIGHT_TICKS,
+            stuck: false,
+        })
+    }
+
+    /// Heavy, knocks targets back further and shatters fragile tiles
+    pub fn rock(pos: EntityPosition, vel: Vector, owner: Option<EntityId>) -> ProjectileEntity {
+        ProjectileEntity::new(EntityType::Rock, pos, vel, (6.0, 6.0), Color::new(0xff, 0x7a, 0x7a, 0x78), Projectile {
+            damage: 5,
+            knockback: 12.0,
+            breaks_fragile: true,
+            owner,
+            ticks_left: FLIGHT_TICKS,
+            stuck: false,
+        })
+    }
+
+    fn new(kind: EntityType, pos: EntityPosition, vel: Vector, size: (f32, f32), color: Color, projectile: Projectile) -> ProjectileEntity {
+        ProjectileEntity {
+            kind,
+            position: Position(pos),
+            velocity: Velocity(vel),
+            collider: Collider { width: size.0, height: size.1 },
+            renderable: Renderable {
+                color,
+                width: size.0,
+                height: size.1,
+                show_health: false,
+            },
+            projectile,
+        }
+    }
+}
+
+impl Bundle for ProjectileEntity {
+    fn insert_into(self, ecs: &mut Ecs, id: EntityId) {
+        ecs.insert(id, self.kind);
+        ecs.insert(id, self.position);
+        ecs.insert(id, self.velocity);
+        ecs.insert(id, self.collider);
+        ecs.insert(id, self.renderable);
+        ecs.insert(id, self.projectile);
+    }
+}
@@ -0,0 +1,65 @@
+use raqote::Color;
+use crate::ai::{Brain, Gait, Temperament};
+use crate::ecs::{Bundle, Ecs, EntityId};
+use crate::ecs::component::{Animator, Collider, ContactDamage, Facing, Health, Position, Renderable, Velocity};
+use crate::entity::EntityType;
+use crate::{EntityPosition, FloatVector2D};
+
+const SLIME_WIDTH: f32 = 16.0;
+const SLIME_HEIGHT: f32 = 12.0;
+const MAX_HEALTH: u8 = 20;
+const INVULNERABLE_TICKS: u32 = 8;
+const HOP_SPEED: f32 = 6.0;
+const HOP_STRENGTH: f32 = 12.0;
+const SIGHT_RANGE: f32 = 120.0;
+const CONTACT_DAMAGE: u8 = 5;
+const CONTACT_COOLDOWN: u32 = 15;
+
+/// Hostile mob that hops towards the player and hurts it on contact
+pub struct SlimeEntity {
+    pub position: Position,
+    pub velocity: Velocity,
+    pub collider: Collider,
+    pub health: Health,
+    pub facing: Facing,
+    pub animator: Animator,
+    pub renderable: Renderable,
+    pub brain: Brain,
+    pub contact: ContactDamage,
+}
+
+impl SlimeEntity {
+    pub fn new(pos: EntityPosition) -> SlimeEntity {
+        SlimeEntity {
+            position: Position(pos),
+            velocity: Velocity(FloatVector2D(0.0, 0.0)),
+            collider: Collider { width: SLIME_WIDTH, height: SLIME_HEIGHT },
+            health: Health::new(MAX_HEALTH).with_invulnerability(INVULNERABLE_TICKS),
+            facing: Facing::Right,
+            animator: Animator::new(),
+            renderable: Renderable {
+                color: Color::new(0xff, 0x4c, 0xc2, 0x4a),
+                width: SLIME_WIDTH,
+                height: SLIME_HEIGHT,
+                show_health: true,
+            },
+            brain: Brain::new(Temperament::Hostile, Gait::Hop(HOP_STRENGTH), HOP_SPEED, SIGHT_RANGE),
+            contact: ContactDamage::new(CONTACT_DAMAGE, CONTACT_COOLDOWN),
+        }
+    }
+}
+
+impl Bundle for SlimeEntity {
+    fn insert_into(self, ecs: &mut Ecs, id: EntityId) {
+        ecs.insert(id, EntityType::Slime);
+        ecs.insert(id, self.position);
+        ecs.insert(id, self.velocity);
+        ecs.insert(id, self.collider);
+        ecs.insert(id, self.health);
+        ecs.insert(id, self.facing);
+        ecs.insert(id, self.animator);
+        ecs.insert(id, self.renderable);
+        ecs.insert(id, self.brain);
+        ecs.insert(id, self.contact);
+    }
+}
@@ -1,16 +1,80 @@
-use std::cell::{RefCell, RefMut};
+pub mod session;
+pub mod menu;
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
 use std::rc::Rc;
-use std::time::{Duration, Instant};
+use std::sync::atomic::Ordering;
+use std::time::Instant;
 use font_kit::font::Font;
-use minifb::{Key, MouseButton, MouseMode, Window};
+use minifb::Window;
 use raqote::{Color, DrawOptions, DrawTarget, Point, SolidSource, Source};
-use crate::{EntityPosition, GRID_SIZE, MAX_FPS, TilePosition, TICK_RATE, TILE_SIZE};
-use crate::entity::Entity;
+use crate::{DAY_LENGTH, EntityPosition, GRID_SIZE, MAX_FPS, TICK_RATE, WORLD_WIDTH};
+use crate::console::{CommandContext, Console};
+use crate::ecs::EntityId;
 use crate::entity::player::PlayerEntity;
+use crate::game::menu::{MenuAction, WorldForm};
+use crate::game::session::Session;
+use crate::save;
+use crate::script::ScriptHost;
+use crate::ui::input::{CharQueue, UiInput, UiKey};
+use crate::ui::{self, Ui, UiState};
 use crate::world::World;
 
 pub const DEFAULT_TICK_RATE: u8 = 30;
 pub const DEFAULT_MAX_FPS: u8 = 60;
+/// Where the player appears in new worlds and saves without a player
+pub const PLAYER_SPAWN: EntityPosition = EntityPosition(40.0, 220.0);
+
+/// Seed for a new world from what was typed, random when empty. Any text works as a seed.
+pub fn parse_seed(seed: &str) -> u64 {
+    match seed.trim() {
+        "" => rand::random(),
+        seed => seed.parse().unwrap_or_else(|_| {
+            let mut hasher = DefaultHasher::new();
+            seed.hash(&mut hasher);
+            hasher.finish()
+        }),
+    }
+}
+
+/// Generates a world with the player in it
+pub fn new_world(seed: u64) -> (Rc<RefCell<World>>, EntityId) {
+    let world = World::with_seed(WORLD_WIDTH, GRID_SIZE, seed);
+    world.borrow_mut().clock_mut().set_day_length(*DAY_LENGTH.get().unwrap());
+    let player = world.borrow_mut().spawn(PlayerEntity::new(Some(PLAYER_SPAWN)));
+    (world, player)
+}
+
+/// A screen of the game. The top of the stack gets input and is updated, the rest wait under it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+    MainMenu,
+    WorldSelect,
+    Playing,
+    /// Drawn over the frozen world
+    Paused,
+    Settings,
+    /// The developer console, drawn over the frozen world
+    Console,
+}
+
+/// Options changed from the settings menu
+pub struct Settings {
+    /// Whether the debug overlay starts open in a world
+    pub show_debug: bool,
+    /// Opens the pause menu when the window loses focus
+    pub pause_on_focus_loss: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { show_debug: false, pause_on_focus_loss: true }
+    }
+}
+
 pub struct Game {
     pub window: Window,
     pub target: DrawTarget,
@@ -18,19 +82,31 @@ pub struct Game {
     last_update: Instant,
     last_render: Instant,
     size: (usize, usize),
-    current_world: Rc<RefCell<World>>,
-    player: Rc<RefCell<Box<dyn Entity>>>,
-    is_mouse_down: bool,
+    states: Vec<GameState>,
+    /// The world being played, kept while paused
+    session: Option<Session>,
+    settings: Settings,
+    saves_dir: PathBuf,
+    ui: UiState,
+    chars: CharQueue,
+    world_form: WorldForm,
+    /// Result of the last save or load, shown in the menus
+    status: Option<String>,
+    console: Console,
+    /// Commands run whenever a world starts
+    script: Option<PathBuf>,
+    scripts: Rc<ScriptHost>,
+    quit: bool,
 }
-const MOVE_SPEED: f32 = 10.0;
 
 impl Game {
-    pub fn new( window: Window, target: DrawTarget, font: Font) -> Game {
-        let world = World::new(GRID_SIZE, GRID_SIZE);
-        let player_pos = EntityPosition(40.0, 220.0);
-        let player = world.borrow_mut().add_entity(PlayerEntity::new(Some(player_pos)));
+    pub fn new(mut window: Window, target: DrawTarget, font: Font, saves_dir: PathBuf, script: Option<PathBuf>, scripts: ScriptHost) -> Game {
+        let chars = CharQueue::default();
+        window.set_input_callback(Box::new(chars.clone()));
         let size = window.get_size();
-        println!("tickrate = {} | max fps = {}", TICK_RATE.get().unwrap(), MAX_FPS.get().unwrap());
+        println!("tickrate = {} | max fps = {}", TICK_RATE.load(Ordering::Relaxed), MAX_FPS.get().unwrap());
+        let mut console = Console::new();
+        scripts.register_commands(&mut console.registry);
         Game {
             window,
             target,
@@ -38,30 +114,36 @@ impl Game {
             last_update: Instant::now(),
             last_render: Instant::now(),
             size,
-            player,
-            current_world: world,
-            is_mouse_down: false
+            states: vec![GameState::MainMenu],
+            session: None,
+            settings: Settings::default(),
+            saves_dir,
+            ui: UiState::default(),
+            chars,
+            world_form: WorldForm::default(),
+            status: None,
+            console,
+            script,
+            scripts: Rc::new(scripts),
+            quit: false,
         }
     }
 
     pub fn draw_text(&mut self, pos: Point, scale: f32, text: &str, src: &Source, options: Option<DrawOptions>) {
-        self.target.draw_text(&self.font, scale, text, pos, src, &options.unwrap_or_default());
+        ui::draw_text(&mut self.target, &self.font, scale, text, pos, src, &options.unwrap_or_default());
     }
     pub fn draw_text_simple(&mut self, pos: Point, scale: f32, text: &str, color: Color) {
         let source = Source::Solid(SolidSource::from(color));
         self.draw_text(pos, scale, text, &source, None);
     }
 
-    pub fn world(&self) -> Rc<RefCell<World>> {
-        self.current_world.clone()
+    pub fn state(&self) -> GameState {
+        *self.states.last().unwrap()
     }
 
-    pub fn world_mut(&mut self) -> RefMut<'_, World> {
-        self.current_world.borrow_mut()
-    }
-
-    pub fn set_world(&mut self, world: World) {
-
+    /// Whether quit was picked from a menu
+    pub fn should_quit(&self) -> bool {
+        self.quit
     }
 
     pub fn render(&mut self) {
@@ -69,47 +151,166 @@ impl Game {
         if &self.last_render.elapsed().as_secs_f32() < MAX_FPS.get().unwrap() {
             return;
         }
-        self.target.clear(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0xff, 0xff));
-        self.current_world.borrow().render(&mut self.target, &self.font);
+        // Window events are refreshed once per frame, so they are read here rather than in update
+        self.handle_resize();
+        let input = UiInput::from_window(&self.window, &self.chars);
+        for line in self.scripts.take_output() {
+            println!("{}", line);
+            self.console.print(line);
+        }
+        let state = self.state();
+        match (&mut self.session, state) {
+            (Some(session), GameState::Playing | GameState::Paused) => {
+                if state == GameState::Playing {
+                    session.zoom(input.scroll);
+                }
+                session.render(&mut self.target, &self.font, &self.window);
+            }
+            // Settings opened from the pause menu stay over the world
+            (Some(session), GameState::Settings | GameState::Console) => session.render(&mut self.target, &self.font, &self.window),
+            _ => self.target.clear(SolidSource::from_unpremultiplied_argb(0xff, 0x20, 0x28, 0x38)),
+        }
+        let action = self.render_menu(state, &input);
         self.window.update_with_buffer(self.target.get_data(), self.size.0, self.size.1).unwrap();
         self.last_render = Instant::now();
+        if let Some(action) = action {
+            self.apply(action);
+        }
+    }
+
+    /// Draws the menu of the current state, returning what it asked for
+    fn render_menu(&mut self, state: GameState, input: &UiInput) -> Option<MenuAction> {
+        if state == GameState::Playing {
+            if input.pressed(UiKey::Backquote) {
+                return Some(MenuAction::Push(GameState::Console));
+            }
+            return input.pressed(UiKey::Escape).then_some(MenuAction::Push(GameState::Paused));
+        }
+        let (width, height) = (self.size.0 as f32, self.size.1 as f32);
+        let origin = if state == GameState::Console { (0.0, height - menu::CONSOLE_HEIGHT) } else { (0.0, 0.0) };
+        let typing = self.ui.has_focus();
+        let mut ui = Ui::new(&mut self.target, &self.font, input, &mut self.ui, origin);
+        match state {
+            GameState::MainMenu => menu::main_menu(&mut ui),
+            GameState::WorldSelect => menu::world_select(&mut ui, &mut self.world_form, self.status.as_deref(), typing),
+            GameState::Paused => menu::paused(&mut ui, self.status.as_deref()),
+            GameState::Settings => menu::settings(&mut ui, &mut self.settings),
+            GameState::Console => menu::console(&mut ui, &mut self.console, width),
+            GameState::Playing => None,
+        }
     }
 
     pub fn update(&mut self) {
         // Only run 1/UPDATE_RATE times a second
-        if &self.last_update.elapsed().as_secs_f32() < TICK_RATE.get().unwrap() {
+        if self.last_update.elapsed().as_secs_f32() < 1.0 / TICK_RATE.load(Ordering::Relaxed) as f32 {
             return;
         }
-        if self.window.get_mouse_down(MouseButton::Left) {
-            if !self.is_mouse_down {
-                self.is_mouse_down = true;
-                if let Some(cursor_pos) = self.window.get_mouse_pos(MouseMode::Clamp) {
-                    let pos = TilePosition((cursor_pos.0 / TILE_SIZE) as usize, (cursor_pos.1 / TILE_SIZE) as usize);
-                    println!("clicked at ({:.2},{:.2}) / tile {}", cursor_pos.0, cursor_pos.1, pos);
+        self.last_update = Instant::now();
+        if self.state() != GameState::Playing {
+            return;
+        }
+        if self.settings.pause_on_focus_loss && !self.window.is_active() {
+            self.apply(MenuAction::Push(GameState::Paused));
+            return;
+        }
+        if let Some(session) = &mut self.session {
+            session.update(&self.window);
+        }
+    }
+
+    pub fn apply(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::Push(state) => {
+                if state == GameState::WorldSelect {
+                    self.world_form.saves = save::list(&self.saves_dir);
+                    self.world_form.selected = None;
+                }
+                if state == GameState::Paused {
+                    self.status = None;
                 }
+                self.states.push(state);
             }
-        } else {
-            self.is_mouse_down = false;
-        }
-        self.window.get_keys_pressed(minifb::KeyRepeat::Yes).iter().for_each(|key|
-            match key {
-                Key::W => {
-                    self.player.borrow_mut().mv_rel((0.0, MOVE_SPEED));
-                },
-                Key::S => {
-                    self.player.borrow_mut().mv_rel((0.0, -MOVE_SPEED));
-                },
-                Key::A => {
-                    self.player.borrow_mut().mv_rel((-MOVE_SPEED, 0.0));
+            MenuAction::Pop => {
+                if self.states.len() > 1 && self.states.pop() == Some(GameState::Settings) {
+                    if let Some(session) = &mut self.session {
+                        session.debug.visible = self.settings.show_debug;
+                    }
                 }
-                ,Key::D => {
-                    self.player.borrow_mut().mv_rel((MOVE_SPEED, 0.0));
+            }
+            MenuAction::Create { name, seed } => self.create_world(&name, &seed),
+            MenuAction::Load(name) => {
+                let path = save::path_for(&self.saves_dir, &name);
+                match save::load(&path, PLAYER_SPAWN) {
+                    Ok((world, player)) => self.play(Session::new(world, player, path, self.scripts.clone(), self.size)),
+                    Err(e) => self.status = Some(e.to_string()),
+                }
+            }
+            MenuAction::Save => {
+                if let Some(session) = &self.session {
+                    self.status = Some(match session.save() {
+                        Ok(()) => format!("Saved {}", session.name()),
+                        Err(e) => e.to_string(),
+                    });
                 }
-                _ => (),
             }
-        );
+            MenuAction::QuitToMenu => {
+                self.session = None;
+                self.states = vec![GameState::MainMenu];
+            }
+            MenuAction::Command(line) => {
+                if let Some(session) = &self.session {
+                    let mut ctx = CommandContext { world: &session.world(), player: session.player(), scripts: &self.scripts };
+                    self.console.execute(&line, &mut ctx);
+                }
+            }
+            MenuAction::Quit => self.quit = true,
+        }
+    }
+
+    /// Generates a world and saves it straight away so it shows up in the world list
+    fn create_world(&mut self, name: &str, seed: &str) {
+        let name = if name.trim().is_empty() { "world" } else { name.trim() };
+        // Don't overwrite an existing world with the same name
+        let mut path = save::path_for(&self.saves_dir, name);
+        let mut copy = 1;
+        while path.exists() {
+            copy += 1;
+            path = save::path_for(&self.saves_dir, &format!("{} {}", name, copy));
+        }
+        let (world, player) = new_world(parse_seed(seed));
+        let session = Session::new(world, player, path, self.scripts.clone(), self.size);
+        if let Err(e) = session.save() {
+            println!("could not save new world: {}", e);
+        }
+        self.world_form.name.clear();
+        self.world_form.seed.clear();
+        self.play(session);
+    }
+
+    fn play(&mut self, mut session: Session) {
+        session.debug.visible = self.settings.show_debug;
+        if let Some(script) = &self.script {
+            let mut ctx = CommandContext { world: &session.world(), player: session.player(), scripts: &self.scripts };
+            match self.console.registry.run_script(script, &mut ctx) {
+                Ok(output) => output.into_iter().for_each(|line| self.console.print(line)),
+                Err(e) => self.console.print(format!("could not run {}: {}", script.display(), e)),
+            }
+        }
+        self.session = Some(session);
+        self.status = None;
+        self.states = vec![GameState::Playing];
+    }
 
-        self.current_world.borrow_mut().update();
-        self.last_update = Instant::now();
+    /// Recreates the draw target when the window size changed
+    fn handle_resize(&mut self) {
+        let size = self.window.get_size();
+        if size == self.size || size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        self.size = size;
+        self.target = DrawTarget::new(size.0 as i32, size.1 as i32);
+        if let Some(session) = &mut self.session {
+            session.set_viewport(size);
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,177 @@
+use std::sync::atomic::Ordering;
+use crate::game::{GameState, Settings};
+use crate::ui::input::UiKey;
+use crate::console::Console;
+use crate::ui::Ui;
+use crate::TICK_RATE;
+
+const MENU_WIDTH: f32 = 240.0;
+/// Wide enough for a slider and its label
+const SETTINGS_WIDTH: f32 = 320.0;
+/// Fastest tick rate the settings slider goes up to
+const MAX_TICK_RATE: f32 = 120.0;
+const BUTTON_WIDTH: f32 = MENU_WIDTH - 12.0;
+const SAVE_ROWS: usize = 6;
+pub const CONSOLE_HEIGHT: f32 = 204.0;
+/// Log lines that fit above the console's input
+const CONSOLE_LINES: usize = 6;
+/// A log line's label plus the space after it
+const CONSOLE_LINE_HEIGHT: f32 = 26.0;
+
+/// What a menu asks the game to do
+pub enum MenuAction {
+    Push(GameState),
+    Pop,
+    /// Creates and saves a new world, then plays it
+    Create { name: String, seed: String },
+    Load(String),
+    Save,
+    /// Runs a console command
+    Command(String),
+    /// Leaves the world for the main menu without saving
+    QuitToMenu,
+    Quit,
+}
+
+/// What is being typed and picked on the world select screen
+#[derive(Default)]
+pub struct WorldForm {
+    pub name: String,
+    pub seed: String,
+    pub saves: Vec<String>,
+    pub selected: Option<usize>,
+}
+
+pub fn main_menu(ui: &mut Ui) -> Option<MenuAction> {
+    let mut action = None;
+    ui.centered_panel(MENU_WIDTH, 140.0, |ui| {
+        ui.label("Grid Test");
+        if ui.button_sized("Play", BUTTON_WIDTH) {
+            action = Some(MenuAction::Push(GameState::WorldSelect));
+        }
+        if ui.button_sized("Settings", BUTTON_WIDTH) {
+            action = Some(MenuAction::Push(GameState::Settings));
+        }
+        if ui.button_sized("Quit", BUTTON_WIDTH) {
+            action = Some(MenuAction::Quit);
+        }
+    });
+    action
+}
+
+/// `typing` is whether a text field had focus, Escape then only leaves the field
+pub fn world_select(ui: &mut Ui, form: &mut WorldForm, status: Option<&str>, typing: bool) -> Option<MenuAction> {
+    let mut action = None;
+    ui.centered_panel(MENU_WIDTH * 1.5, 350.0, |ui| {
+        ui.label("Saved worlds");
+        ui.scroll_list("saves", &form.saves, SAVE_ROWS, MENU_WIDTH * 1.5 - 12.0, &mut form.selected);
+        if ui.button("Load") {
+            if let Some(name) = form.selected.and_then(|i| form.saves.get(i)) {
+                action = Some(MenuAction::Load(name.clone()));
+            }
+        }
+        ui.space(4.0);
+        ui.label("New world");
+        ui.row(|ui| {
+            ui.label("Name");
+            ui.text_input("name", &mut form.name, 140.0);
+        });
+        ui.row(|ui| {
+            ui.label("Seed");
+            ui.text_input("seed", &mut form.seed, 140.0);
+        });
+        ui.row(|ui| {
+            if ui.button("Create") {
+                action = Some(MenuAction::Create { name: form.name.clone(), seed: form.seed.clone() });
+            }
+            if ui.button("Back") {
+                action = Some(MenuAction::Pop);
+            }
+        });
+        if let Some(status) = status {
+            ui.label(status);
+        }
+    });
+    if action.is_none() && !typing && ui.input().pressed(UiKey::Escape) {
+        action = Some(MenuAction::Pop);
+    }
+    action
+}
+
+pub fn paused(ui: &mut Ui, status: Option<&str>) -> Option<MenuAction> {
+    let mut action = None;
+    ui.centered_panel(MENU_WIDTH, 190.0, |ui| {
+        ui.label("Paused");
+        if ui.button_sized("Resume", BUTTON_WIDTH) {
+            action = Some(MenuAction::Pop);
+        }
+        if ui.button_sized("Save", BUTTON_WIDTH) {
+            action = Some(MenuAction::Save);
+        }
+        if ui.button_sized("Settings", BUTTON_WIDTH) {
+            action = Some(MenuAction::Push(GameState::Settings));
+        }
+        if ui.button_sized("Quit to menu", BUTTON_WIDTH) {
+            action = Some(MenuAction::QuitToMenu);
+        }
+        if let Some(status) = status {
+            ui.label(status);
+        }
+    });
+    if action.is_none() && ui.input().pressed(UiKey::Escape) {
+        action = Some(MenuAction::Pop);
+    }
+    action
+}
+
+pub fn settings(ui: &mut Ui, settings: &mut Settings) -> Option<MenuAction> {
+    let mut action = None;
+    ui.centered_panel(SETTINGS_WIDTH, 190.0, |ui| {
+        ui.label("Settings");
+        ui.column(|ui| {
+            ui.checkbox("Show debug overlay", &mut settings.show_debug);
+            ui.checkbox("Pause when unfocused", &mut settings.pause_on_focus_loss);
+        });
+        ui.space(4.0);
+        let mut rate = TICK_RATE.load(Ordering::Relaxed) as f32;
+        if ui.slider("Tick rate", &mut rate, 1.0..=MAX_TICK_RATE) {
+            TICK_RATE.store(rate.round() as u8, Ordering::Relaxed);
+        }
+        if ui.button_sized("Back", BUTTON_WIDTH) {
+            action = Some(MenuAction::Pop);
+        }
+    });
+    if action.is_none() && ui.input().pressed(UiKey::Escape) {
+        action = Some(MenuAction::Pop);
+    }
+    action
+}
+
+/// The console along the bottom of the screen, its input always focused
+pub fn console(ui: &mut Ui, console: &mut Console, width: f32) -> Option<MenuAction> {
+    if ui.input().pressed(UiKey::Escape) || ui.input().pressed(UiKey::Backquote) {
+        return Some(MenuAction::Pop);
+    }
+    if ui.input().pressed(UiKey::Up) {
+        console.browse_history(true);
+    } else if ui.input().pressed(UiKey::Down) {
+        console.browse_history(false);
+    }
+    let mut action = None;
+    ui.panel(width, CONSOLE_HEIGHT, |ui| {
+        let log = console.log();
+        for line in &log[log.len().saturating_sub(CONSOLE_LINES)..] {
+            ui.label(line);
+        }
+        ui.focus("console");
+        let skip = CONSOLE_LINES.saturating_sub(log.len());
+        if skip > 0 {
+            // Keep the input at the bottom while the log is short
+            ui.space(skip as f32 * CONSOLE_LINE_HEIGHT - 6.0);
+        }
+        if ui.text_input("console", &mut console.input, width - 12.0) {
+            action = Some(MenuAction::Command(console.submit()));
+        }
+    });
+    action
+}
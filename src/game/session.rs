@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Instant;
+use font_kit::font::Font;
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use raqote::DrawTarget;
+use crate::{EntityPosition, Velocity};
+use crate::camera::Camera;
+use crate::ecs::component::Position;
+use crate::ecs::EntityId;
+use crate::ecs::system;
+use crate::debug::{DebugOverlay, FrameStats};
+use crate::hud::Hud;
+use crate::entity::projectile::ProjectileEntity;
+use crate::pathfinding::PathOptions;
+use crate::save::{self, SaveError};
+use crate::script::ScriptHost;
+use crate::tile::{self, TileType};
+use crate::world::World;
+
+/// Speed in pixels per tick of arrows fired by the player
+const ARROW_SPEED: f32 = 12.0;
+/// Speed in pixels per tick of rocks thrown by the player
+const ROCK_SPEED: f32 = 8.0;
+
+/// A world being played, with everything that views and controls it
+pub struct Session {
+    world: Rc<RefCell<World>>,
+    player: EntityId,
+    /// Where the world is saved to
+    path: PathBuf,
+    scripts: Rc<ScriptHost>,
+    camera: Camera,
+    pub hud: Hud,
+    pub debug: DebugOverlay,
+    stats: FrameStats,
+    is_mouse_down: bool,
+    is_right_mouse_down: bool,
+}
+
+impl Session {
+    pub fn new(world: Rc<RefCell<World>>, player: EntityId, path: PathBuf, scripts: Rc<ScriptHost>, viewport: (usize, usize)) -> Session {
+        let mut session = Session {
+            world,
+            player,
+            path,
+            scripts,
+            camera: Camera::new(viewport),
+            hud: Hud::new(),
+            debug: DebugOverlay::default(),
+            stats: FrameStats::new(),
+            is_mouse_down: false,
+            is_right_mouse_down: false,
+        };
+        session.follow_player();
+        session
+    }
+
+    pub fn world(&self) -> Rc<RefCell<World>> {
+        self.world.clone()
+    }
+
+    pub fn player(&self) -> EntityId {
+        self.player
+    }
+
+    pub fn save(&self) -> Result<(), SaveError> {
+        save::save(&self.world.borrow(), self.player, &self.path)
+    }
+
+    /// Name of the save, from its file name
+    pub fn name(&self) -> String {
+        self.path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned())
+    }
+
+    pub fn set_viewport(&mut self, size: (usize, usize)) {
+        self.camera.set_viewport(size);
+        self.camera.clamp(self.world.borrow().pixel_size());
+    }
+
+    pub fn zoom(&mut self, scroll: f32) {
+        if scroll != 0.0 {
+            self.camera.step_zoom(scroll.signum() as isize);
+            self.camera.clamp(self.world.borrow().pixel_size());
+        }
+    }
+
+    /// Draws the world and the HUD
+    pub fn render(&mut self, target: &mut DrawTarget, font: &Font, window: &Window) {
+        let world = self.world.borrow();
+        let view = self.camera.transform();
+        world.render(target, font, &view);
+        self.debug.render_layers(target, font, &world, &view);
+        self.hud.update(&world, self.player);
+        self.hud.debug_panel.visible = self.debug.visible;
+        if self.debug.visible {
+            let cursor = window.get_mouse_pos(MouseMode::Discard).map(|pos| view.screen_to_tile(pos));
+            self.hud.debug_panel.lines = self.debug.lines(&world, self.player, &self.stats, cursor);
+        }
+        drop(world);
+        self.hud.render(target, font);
+        self.stats.frame();
+    }
+
+    /// Handles the player's input and advances the world a tick
+    pub fn update(&mut self, window: &Window) {
+        if window.get_mouse_down(MouseButton::Left) {
+            if !self.is_mouse_down {
+                self.is_mouse_down = true;
+                if let Some(cursor_pos) = window.get_mouse_pos(MouseMode::Clamp) {
+                    let pos = self.camera.transform().screen_to_tile(cursor_pos);
+                    println!("clicked at ({:.2},{:.2}) / tile {}", cursor_pos.0, cursor_pos.1, pos);
+                    self.launch_at(cursor_pos, ARROW_SPEED, ProjectileEntity::arrow);
+                }
+            }
+        } else {
+            self.is_mouse_down = false;
+        }
+        if window.get_mouse_down(MouseButton::Right) {
+            if !self.is_right_mouse_down {
+                self.is_right_mouse_down = true;
+                if let Some(cursor_pos) = window.get_mouse_pos(MouseMode::Clamp) {
+                    self.find_path_to(cursor_pos);
+                }
+            }
+        } else {
+            self.is_right_mouse_down = false;
+        }
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            if let Some(cursor_pos) = window.get_mouse_pos(MouseMode::Discard) {
+                let pos = self.camera.transform().screen_to_tile(cursor_pos);
+                self.scripts.interact(&self.world, self.player, &pos);
+            }
+        }
+        if window.is_key_pressed(Key::Q, KeyRepeat::No) {
+            if let Some(cursor_pos) = window.get_mouse_pos(MouseMode::Discard) {
+                self.place_at(cursor_pos);
+            }
+        }
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            if let Some(cursor_pos) = window.get_mouse_pos(MouseMode::Clamp) {
+                self.launch_at(cursor_pos, ROCK_SPEED, ProjectileEntity::rock);
+            }
+        }
+        if window.is_key_pressed(Key::F, KeyRepeat::Yes) {
+            system::melee::attack(self.world.borrow().ecs(), self.player);
+        }
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            let mut world = self.world.borrow_mut();
+            world.show_paths = !world.show_paths;
+        }
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            self.debug.visible = !self.debug.visible;
+        }
+        if self.debug.visible {
+            let layers = &mut self.debug.layers;
+            for (key, layer) in [(Key::F4, &mut layers.grid), (Key::F5, &mut layers.colliders), (Key::F6, &mut layers.chunks), (Key::F7, &mut layers.light)] {
+                if window.is_key_pressed(key, KeyRepeat::No) {
+                    *layer = !*layer;
+                }
+            }
+        }
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            let mut world = self.world.borrow_mut();
+            world.raining = !world.raining;
+        }
+        let slot_keys = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+        if let Some(slot) = slot_keys.iter().position(|key| window.is_key_pressed(*key, KeyRepeat::No)) {
+            self.hud.hotbar.select(slot);
+        }
+        let keys = window.get_keys_pressed(KeyRepeat::Yes);
+        system::input::run(self.world.borrow().ecs(), &keys);
+
+        let started = Instant::now();
+        self.scripts.tick(&self.world, self.player);
+        self.stats.tick(started.elapsed());
+        self.follow_player();
+    }
+
+    fn follow_player(&mut self) {
+        let world = self.world.borrow();
+        let pos = world.ecs().get::<Position>(self.player).map(|p| p.0.clone());
+        if let Some(pos) = pos {
+            self.camera.follow(&pos, world.pixel_size());
+        }
+    }
+
+    /// Finds a path from the player to the tile under the cursor, shown by the path overlay
+    fn find_path_to(&self, cursor_pos: (f32, f32)) {
+        let world = self.world.borrow();
+        let Some(start) = world.ecs().get::<Position>(self.player).map(|p| p.0.to_tile_coords()) else {
+            return;
+        };
+        let goal = self.camera.transform().screen_to_tile(cursor_pos);
+        world.find_path(&start, &goal, &PathOptions::default());
+    }
+
+    /// Places the tile in the selected hotbar slot under the cursor, if nothing is there yet
+    fn place_at(&self, cursor_pos: (f32, f32)) {
+        let Some(def) = self.hud.hotbar.selected_tile() else {
+            return;
+        };
+        let pos = self.camera.transform().screen_to_tile(cursor_pos);
+        let mut world = self.world.borrow_mut();
+        if !world.get_tile(&pos).is_some_and(|tile| tile.get_type() == &TileType::Empty) {
+            return;
+        }
+        if let Some(tile) = tile::from_name(&def.id) {
+            world.set_tile(&pos, tile);
+        }
+    }
+
+    /// Fires an arrow or throws a rock from the player towards the cursor, made by `projectile`
+    fn launch_at(&self, cursor_pos: (f32, f32), speed: f32, projectile: fn(EntityPosition, Velocity, Option<EntityId>) -> ProjectileEntity) {
+        let mut world = self.world.borrow_mut();
+        let Some(start) = world.ecs().get::<Position>(self.player).map(|p| p.0.clone()) else {
+            return;
+        };
+        let aim = self.camera.transform().screen_to_world(cursor_pos);
+        let (dx, dy) = (aim.0 - start.0, aim.1 - start.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        let vel = Velocity(dx / length * speed, dy / length * speed);
+        world.spawn(projectile(start, vel, Some(self.player)));
+    }
+}
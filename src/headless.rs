@@ -0,0 +1,64 @@
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use crate::console::{CommandContext, CommandRegistry};
+use crate::game::{self, PLAYER_SPAWN};
+use crate::save;
+use crate::script::ScriptHost;
+
+/// Runs console commands against a world without opening a window. The world is loaded from
+/// `saves_dir` or created, `script` runs first, then commands are read from stdin until it closes.
+pub fn run(saves_dir: &Path, name: &str, seed: &str, script: Option<&Path>, scripts: &ScriptHost, save_on_exit: bool) {
+    let path = save::path_for(saves_dir, name);
+    let (world, player) = if path.exists() {
+        match save::load(&path, PLAYER_SPAWN) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        }
+    } else {
+        game::new_world(game::parse_seed(seed))
+    };
+    println!("running {} headless, seed {}", path.display(), world.borrow().seed());
+    let mut registry = CommandRegistry::new();
+    scripts.register_commands(&mut registry);
+    print_output(scripts);
+    if let Some(script) = script {
+        let mut ctx = CommandContext { world: &world, player, scripts };
+        match registry.run_script(script, &mut ctx) {
+            Ok(output) => output.iter().for_each(|line| println!("{}", line)),
+            Err(e) => eprintln!("could not run {}: {}", script.display(), e),
+        }
+    }
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let mut ctx = CommandContext { world: &world, player, scripts };
+        let result = registry.run(&line, &mut ctx);
+        print_output(scripts);
+        match result {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+    println!();
+    if save_on_exit {
+        match save::save(&world.borrow(), player, &path) {
+            Ok(()) => println!("saved {}", path.display()),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+/// Prints what scripts printed
+fn print_output(scripts: &ScriptHost) {
+    scripts.take_output().iter().for_each(|line| println!("{}", line));
+}
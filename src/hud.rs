@@ -0,0 +1,68 @@
+pub mod health_bar;
+pub mod hotbar;
+pub mod debug_panel;
+
+use font_kit::font::Font;
+use raqote::DrawTarget;
+use crate::ecs::component::Health;
+use crate::ecs::EntityId;
+use crate::world::World;
+use crate::hud::debug_panel::DebugPanel;
+use crate::hud::health_bar::HealthBar;
+use crate::hud::hotbar::Hotbar;
+
+/// Space kept between widgets and the edge of the screen
+const MARGIN: f32 = 10.0;
+
+/// Something drawn in screen space on top of the world
+pub trait Widget {
+    /// Width and height in screen pixels
+    fn size(&self, font: &Font) -> (f32, f32);
+    /// Draws the widget with its top left corner at `pos`
+    fn render(&self, target: &mut DrawTarget, font: &Font, pos: (f32, f32));
+}
+
+/// The screen space layer drawn after the world. Widgets copy what they show from the world in
+/// `update`, so entities never draw anything outside of their own body.
+pub struct Hud {
+    pub health_bar: HealthBar,
+    pub hotbar: Hotbar,
+    pub debug_panel: DebugPanel,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            health_bar: HealthBar::default(),
+            hotbar: Hotbar::default(),
+            debug_panel: DebugPanel::default(),
+        }
+    }
+
+    /// Reads the state of the player the widgets show
+    pub fn update(&mut self, world: &World, player: EntityId) {
+        let ecs = world.ecs();
+        let health = ecs.get::<Health>(player);
+        self.health_bar.set(health.as_ref().map_or(0, |h| h.current), health.as_ref().map_or(0, |h| h.max));
+    }
+
+    /// Lays the widgets out against the edges of the target and draws them
+    pub fn render(&self, target: &mut DrawTarget, font: &Font) {
+        let (width, height) = (target.width() as f32, target.height() as f32);
+        let (_, bar_height) = self.health_bar.size(font);
+        self.health_bar.render(target, font, (MARGIN, MARGIN));
+
+        let (hotbar_width, hotbar_height) = self.hotbar.size(font);
+        self.hotbar.render(target, font, ((width - hotbar_width) / 2.0, height - hotbar_height - MARGIN));
+
+        if self.debug_panel.visible {
+            self.debug_panel.render(target, font, (MARGIN, MARGIN * 2.0 + bar_height));
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Hud::new()
+    }
+}
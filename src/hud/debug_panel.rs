@@ -0,0 +1,27 @@
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
+use crate::hud::Widget;
+use crate::ui;
+
+const LINE_HEIGHT: f32 = 20.0;
+const WIDTH: f32 = 320.0;
+
+/// Lines of debug text, one under the other
+#[derive(Default)]
+pub struct DebugPanel {
+    pub lines: Vec<String>,
+    pub visible: bool,
+}
+
+impl Widget for DebugPanel {
+    fn size(&self, _font: &Font) -> (f32, f32) {
+        (WIDTH, self.lines.len() as f32 * LINE_HEIGHT)
+    }
+
+    fn render(&self, target: &mut DrawTarget, font: &Font, pos: (f32, f32)) {
+        let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0, 0));
+        for (i, line) in self.lines.iter().enumerate() {
+            ui::draw_text(target, font, 13., line, Point::new(pos.0, pos.1 + (i + 1) as f32 * LINE_HEIGHT - 6.0), &source, &DrawOptions::new());
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
+use crate::hud::Widget;
+use crate::ui;
+
+const WIDTH: f32 = 160.0;
+const HEIGHT: f32 = 14.0;
+
+/// The player's health as a filling bar with the numbers on top
+#[derive(Default)]
+pub struct HealthBar {
+    current: u8,
+    max: u8,
+}
+
+impl HealthBar {
+    pub fn set(&mut self, current: u8, max: u8) {
+        self.current = current;
+        self.max = max;
+    }
+}
+
+impl Widget for HealthBar {
+    fn size(&self, _font: &Font) -> (f32, f32) {
+        (WIDTH, HEIGHT)
+    }
+
+    fn render(&self, target: &mut DrawTarget, font: &Font, pos: (f32, f32)) {
+        let fill = if self.max == 0 { 0.0 } else { self.current as f32 / self.max as f32 };
+        let options = DrawOptions::new();
+        target.fill_rect(pos.0, pos.1, WIDTH, HEIGHT, &Source::Solid(SolidSource::from_unpremultiplied_argb(0xc0, 0x20, 0x20, 0x20)), &options);
+        target.fill_rect(pos.0 + 1.0, pos.1 + 1.0, (WIDTH - 2.0) * fill, HEIGHT - 2.0,
+                         &Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xd0, 0x30, 0x30)), &options);
+        ui::draw_text(target, font, 12., &format!("{} / {} HP", self.current, self.max), Point::new(pos.0 + 4.0, pos.1 + HEIGHT - 3.0),
+                         &Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0xff, 0xff)), &options);
+    }
+}
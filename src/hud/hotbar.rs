@@ -0,0 +1,71 @@
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
+use crate::hud::Widget;
+use crate::sprite;
+use crate::tile::registry::{self, TileDef};
+use crate::ui;
+
+pub const SLOTS: usize = 9;
+const SLOT_SIZE: f32 = 32.0;
+const SLOT_GAP: f32 = 4.0;
+/// Space between the slot border and the tile drawn in it
+const PADDING: f32 = 6.0;
+
+/// A row of slots holding tiles, one of which is selected
+pub struct Hotbar {
+    pub slots: [Option<&'static TileDef>; SLOTS],
+    selected: usize,
+}
+
+impl Hotbar {
+    /// Selects a slot, ignoring indices past the last slot
+    pub fn select(&mut self, slot: usize) {
+        if slot < SLOTS {
+            self.selected = slot;
+        }
+    }
+
+    /// The tile the player places, if the selected slot holds one
+    pub fn selected_tile(&self) -> Option<&'static TileDef> {
+        self.slots[self.selected]
+    }
+}
+
+impl Default for Hotbar {
+    fn default() -> Self {
+        // Tiles missing from the registry leave their slot empty
+        let mut slots = [None; SLOTS];
+        for (slot, id) in slots.iter_mut().zip(["dirt", "stone", "grass", "glass", "torch"]) {
+            *slot = registry::def(id);
+        }
+        Hotbar { slots, selected: 0 }
+    }
+}
+
+impl Widget for Hotbar {
+    fn size(&self, _font: &Font) -> (f32, f32) {
+        (SLOTS as f32 * (SLOT_SIZE + SLOT_GAP) - SLOT_GAP, SLOT_SIZE)
+    }
+
+    fn render(&self, target: &mut DrawTarget, font: &Font, pos: (f32, f32)) {
+        let options = DrawOptions::new();
+        let border = Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xf0, 0xd0, 0x40));
+        let background = Source::Solid(SolidSource::from_unpremultiplied_argb(0xa0, 0x20, 0x20, 0x20));
+        let label = Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0xff, 0xff));
+        for (i, slot) in self.slots.iter().enumerate() {
+            let x = pos.0 + i as f32 * (SLOT_SIZE + SLOT_GAP);
+            if i == self.selected {
+                target.fill_rect(x - 2.0, pos.1 - 2.0, SLOT_SIZE + 4.0, SLOT_SIZE + 4.0, &border, &options);
+            }
+            target.fill_rect(x, pos.1, SLOT_SIZE, SLOT_SIZE, &background, &options);
+            if let Some(def) = slot {
+                let (tx, ty, size) = (x + PADDING, pos.1 + PADDING, SLOT_SIZE - PADDING * 2.0);
+                match sprite::atlas().and_then(|atlas| atlas.tile(def.sprite())) {
+                    Some(animation) => animation.frame_at(0).draw(target, tx, ty, size, size, false),
+                    None => target.fill_rect(tx, ty, size, size, &Source::Solid(SolidSource::from(def.color())), &options),
+                }
+            }
+            ui::draw_text(target, font, 10., &(i + 1).to_string(), Point::new(x + 2.0, pos.1 + 10.0), &label, &options);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+use crate::coords::ViewTransform;
+use crate::TilePosition;
+use crate::tile::autotile;
+use crate::world::{World, MAX_LIGHT};
+
+/// Light lost per tile when passing through air or a transparent tile
+const AIR_FALLOFF: u8 = 1;
+/// Light lost per tile when passing through a solid tile, so light only reaches a few tiles into the ground
+const SOLID_FALLOFF: u8 = 4;
+/// Opacity of the overlay on a tile with no light at all
+const MAX_DARKNESS: f32 = 0.9;
+
+/// Light levels of every tile, split into light from the sky and light from emissive tiles
+/// so the sky part can be dimmed at night without relighting the world.
+#[derive(Default)]
+pub struct LightMap {
+    width: usize,
+    height: usize,
+    sky: Vec<u8>,
+    block: Vec<u8>,
+}
+
+impl LightMap {
+    /// Lights the whole world
+    pub fn compute(world: &World) -> LightMap {
+        let mut light = LightMap {
+            width: world.width(),
+            height: world.height(),
+            sky: vec![0; world.width() * world.height()],
+            block: vec![0; world.width() * world.height()],
+        };
+        light.relight(world, 0..world.width());
+        light
+    }
+
+    fn index(&self, pos: &TilePosition) -> Option<usize> {
+        (pos.0 < self.width && pos.1 < self.height).then(|| pos.1 * self.width + pos.0)
+    }
+
+    pub fn sky_at(&self, pos: &TilePosition) -> u8 {
+        self.index(pos).map_or(MAX_LIGHT, |i| self.sky[i])
+    }
+
+    pub fn block_at(&self, pos: &TilePosition) -> u8 {
+        self.index(pos).map_or(0, |i| self.block[i])
+    }
+
+    /// Relights the columns a change at position can reach. Light loses at least one level per tile,
+    /// so nothing further than `MAX_LIGHT` columns away can be affected.
+    pub fn update_around(&mut self, world: &World, pos: &TilePosition) {
+        let reach = MAX_LIGHT as usize;
+        self.relight(world, pos.0.saturating_sub(reach)..(pos.0 + reach + 1).min(self.width));
+    }
+
+    /// Recomputes both light layers for a range of columns. The columns just outside the range
+    /// keep their levels and shine into it, so the result matches lighting the whole world.
+    fn relight(&mut self, world: &World, columns: Range<usize>) {
+        if self.width == 0 || columns.is_empty() {
+            return;
+        }
+        let mut sky_queue = VecDeque::new();
+        let mut block_queue = VecDeque::new();
+        for x in columns.clone() {
+            let mut open_sky = true;
+            for y in (0..self.height).rev() {
+                let pos = TilePosition(x, y);
+                let i = y * self.width + x;
+                open_sky &= is_transparent(world, &pos);
+                self.sky[i] = if open_sky { MAX_LIGHT } else { 0 };
+                self.block[i] = emission(world, &pos);
+                if self.sky[i] > 0 {
+                    sky_queue.push_back(pos.clone());
+                }
+                if self.block[i] > 0 {
+                    block_queue.push_back(pos);
+                }
+            }
+        }
+        // The neighbouring columns are already lit, let them spread into the range
+        for x in [columns.start.checked_sub(1), Some(columns.end)].into_iter().flatten() {
+            if x >= self.width {
+                continue;
+            }
+            for y in 0..self.height {
+                sky_queue.push_back(TilePosition(x, y));
+                block_queue.push_back(TilePosition(x, y));
+            }
+        }
+        flood(world, &mut self.sky, self.width, self.height, &columns, sky_queue);
+        flood(world, &mut self.block, self.width, self.height, &columns, block_queue);
+    }
+
+    /// Darkens every visible tile by how little light it gets. `sky_intensity` scales sky light, 1 being full daylight.
+    pub fn render(&self, target: &mut DrawTarget, view: &ViewTransform, sky_intensity: f32) {
+        let (columns, rows) = view.visible_tiles((self.width, self.height));
+        for y in rows {
+            for x in columns.clone() {
+                let pos = TilePosition(x, y);
+                let level = level(self.sky_at(&pos), self.block_at(&pos), sky_intensity);
+                if level >= MAX_LIGHT {
+                    continue;
+                }
+                let darkness = (1.0 - level as f32 / MAX_LIGHT as f32) * MAX_DARKNESS;
+                let (sx, sy, w, h) = view.tile_to_screen(&pos);
+                let source = Source::Solid(SolidSource::from_unpremultiplied_argb((darkness * 255.0) as u8, 0, 0, 0));
+                target.fill_rect(sx, sy, w, h, &source, &DrawOptions::new());
+            }
+        }
+    }
+}
+
+/// The light level of a tile from its two layers
+pub fn level(sky: u8, block: u8, sky_intensity: f32) -> u8 {
+    ((sky as f32 * sky_intensity).round() as u8).max(block)
+}
+
+/// Spreads light from the queued tiles into the columns of the range, only ever raising levels
+fn flood(world: &World, levels: &mut [u8], width: usize, height: usize, columns: &Range<usize>, mut queue: VecDeque<TilePosition>) {
+    while let Some(pos) = queue.pop_front() {
+        let level = levels[pos.1 * width + pos.0];
+        if level == 0 {
+            continue;
+        }
+        for next in autotile::neighbours(&pos) {
+            if !columns.contains(&next.0) || next.1 >= height {
+                continue;
+            }
+            let falloff = if is_transparent(world, &next) { AIR_FALLOFF } else { SOLID_FALLOFF };
+            let i = next.1 * width + next.0;
+            let spread = level.saturating_sub(falloff);
+            if spread > levels[i] {
+                levels[i] = spread;
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+fn is_transparent(world: &World, pos: &TilePosition) -> bool {
+    !world.is_occupied(pos) || world.get_def(pos).is_some_and(|def| def.transparent)
+}
+
+fn emission(world: &World, pos: &TilePosition) -> u8 {
+    world.get_def(pos).map_or(0, |def| def.light)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::tile::base::BaseTile;
+    use super::*;
+
+    /// A world with the tile `place` names at each cell, air where it names none
+    fn world_with(width: usize, height: usize, place: impl Fn(usize, usize) -> Option<&'static str>) -> Rc<RefCell<World>> {
+        World::build(width, height, 1, |world| {
+            for y in 0..height {
+                for x in 0..width {
+                    if let Some(id) = place(x, y) {
+                        world.set_tile(&TilePosition(x, y), BaseTile::named(id).unwrap());
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn torch_light_falls_off_by_one_per_tile() {
+        let world = world_with(20, 6, |x, y| match (x, y) {
+            (_, 0) => Some("bedrock"),
+            (2, 1) => Some("torch"),
+            _ => None,
+        });
+        let light = LightMap::compute(&world.borrow());
+        for y in 1..6 {
+            for x in 0..20 {
+                let pos = TilePosition(x, y);
+                let steps = (pos.0.abs_diff(2) + pos.1.abs_diff(1)) as u8;
+                assert_eq!(light.block_at(&pos), 14u8.saturating_sub(steps), "at {}", pos);
+            }
+        }
+    }
+
+    #[test]
+    fn sky_light_passes_glass_and_stops_at_stone() {
+        // A two tile thick stone roof with a glass skylight in column 6
+        let world = world_with(12, 7, |x, y| match (x, y) {
+            (_, 0) => Some("bedrock"),
+            (6, 4..=5) => Some("glass"),
+            (_, 4..=5) => Some("stone"),
+            _ => None,
+        });
+        let light = LightMap::compute(&world.borrow());
+        for x in 0..12 {
+            assert_eq!(light.sky_at(&TilePosition(x, 6)), MAX_LIGHT);
+        }
+        for y in 1..4 {
+            assert_eq!(light.sky_at(&TilePosition(6, y)), MAX_LIGHT);
+        }
+        // Under the stone, sky light only arrives sideways from the skylight
+        for x in 0..12usize {
+            assert_eq!(light.sky_at(&TilePosition(x, 2)), MAX_LIGHT - x.abs_diff(6) as u8, "at ({},2)", x);
+        }
+    }
+
+    #[test]
+    fn removing_a_torch_darkens_its_cave() {
+        // A closed cave in solid stone, lit only by the torch
+        let world = world_with(12, 8, |x, y| match (x, y) {
+            (2, 1) => Some("torch"),
+            (2..=9, 1..=3) => None,
+            _ => Some("stone"),
+        });
+        let cave = || (2..=9).flat_map(|x| (1..=3).map(move |y| TilePosition(x, y)));
+        assert_eq!(world.borrow().light_at(&TilePosition(5, 1)), 11);
+        world.borrow_mut().remove_tile(&TilePosition(2, 1));
+        let world = world.borrow();
+        for pos in cave() {
+            assert_eq!(world.light_at(&pos), 0, "at {}", pos);
+        }
+        let fresh = LightMap::compute(&world);
+        for pos in cave() {
+            assert_eq!(fresh.block_at(&pos), 0);
+        }
+    }
+}
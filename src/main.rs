@@ -3,9 +3,29 @@ mod tile;
 mod util;
 mod game;
 mod entity;
+mod ecs;
+mod ai;
+mod pathfinding;
+mod spawning;
+mod camera;
+mod coords;
+mod sprite;
+mod lighting;
+mod clock;
+mod particles;
+mod hud;
+mod debug;
+mod ui;
+mod save;
+mod console;
+mod headless;
+mod script;
+mod plugin;
 
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
 use std::sync::OnceLock;
 use minifb::{MouseMode, Window, WindowOptions, ScaleMode, Scale, Key};
 use raqote::{DrawTarget, SolidSource, Source, DrawOptions, PathBuilder, Point, Transform, StrokeStyle, Color};
@@ -16,6 +36,10 @@ use crate::entity::player::PlayerEntity;
 use crate::game::{DEFAULT_MAX_FPS, DEFAULT_TICK_RATE, Game};
 use crate::tile::player::PlayerTile;
 use clap::Parser;
+use crate::sprite::{Atlas, ATLAS};
+use crate::script::ScriptHost;
+use crate::plugin::PluginConfig;
+use crate::tile::registry::{self, TileRegistry};
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -26,18 +50,63 @@ struct Args {
     /// The maximum fps to achieve. 0 for unlimited
     #[arg(long)]
     max_fps: Option<u8>,
+
+    /// Directory containing the texture atlas and tile definitions. Solid colors are drawn when the atlas can't be loaded
+    #[arg(long, default_value = "assets")]
+    assets: PathBuf,
+
+    /// How many ticks a full day and night takes
+    #[arg(long, default_value_t = clock::DEFAULT_DAY_LENGTH)]
+    day_length: u64,
+
+    /// Directory worlds are saved in
+    #[arg(long, default_value = "saves")]
+    saves: PathBuf,
+
+    /// Console commands to run, one per line, whenever a world starts
+    #[arg(long)]
+    script: Option<PathBuf>,
+
+    /// Directory of Rhai scripts adding tiles, behaviours, commands and event handlers
+    #[arg(long, default_value = "scripts")]
+    scripts: PathBuf,
+
+    /// Which plugins to enable, plugins not listed there use their default
+    #[arg(long, default_value = "plugins.toml")]
+    plugins: PathBuf,
+
+    /// Run without a window, reading console commands from stdin after the script
+    #[arg(long)]
+    headless: bool,
+
+    /// World the headless mode loads, created if there is no save with this name
+    #[arg(long, default_value = "world")]
+    world: String,
+
+    /// Seed for worlds created in headless mode
+    #[arg(long, default_value = "")]
+    seed: String,
+
+    /// Save the world when headless mode finishes
+    #[arg(long)]
+    save: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct TilePosition(usize, usize);
 impl Display for TilePosition {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "({},{})", self.0, self.1)
     }
 }
+impl TilePosition {
+    fn to_entity_pos(&self) -> EntityPosition {
+        coords::tile_to_world(self)
+    }
+}
 impl EntityPosition {
     fn to_tile_coords(&self) -> TilePosition {
-        TilePosition((self.0 / TILE_SIZE).round() as usize,((self.1) / TILE_SIZE).round() as usize)
+        coords::world_to_tile(self)
     }
     fn offset(&self, offset: (f32, f32)) -> EntityPosition {
         EntityPosition(self.0 + offset.0, self.1 + offset.1)
@@ -59,16 +128,19 @@ use FloatVector2D as Velocity;
 /// The size of the tile (TILE_SIZExTILE_SIZE)
 const TILE_SIZE: f32 = 20f32;
 const GRID_SIZE: usize = 20;
+/// The width of generated worlds, in tiles
+const WORLD_WIDTH: usize = 64;
 
 const WINDOW_SIZE: usize = TILE_SIZE as usize * GRID_SIZE;
-const RENDER_BOUND: f32 = TILE_SIZE * GRID_SIZE as f32 - TILE_SIZE;
 
 
-pub static TICK_RATE: OnceLock<f32> = OnceLock::new();
+/// Ticks per second, changed at runtime by /tickrate
+pub static TICK_RATE: AtomicU8 = AtomicU8::new(DEFAULT_TICK_RATE);
 pub static MAX_FPS: OnceLock<f32> = OnceLock::new();
+pub static DAY_LENGTH: OnceLock<u64> = OnceLock::new();
 fn main() {
     let args = Args::parse();
-    TICK_RATE.set(1.0 / args.tickrate.unwrap_or(DEFAULT_TICK_RATE) as f32).unwrap();
+    TICK_RATE.store(args.tickrate.unwrap_or(DEFAULT_TICK_RATE).max(1), Ordering::Relaxed);
     let max_fps = args.tickrate.unwrap_or(DEFAULT_MAX_FPS);
     if max_fps == 0 {
         // Set the limit to 1000 for "unlimited" fps
@@ -77,7 +149,46 @@ fn main() {
         MAX_FPS.set(1.0 / args.tickrate.unwrap_or(DEFAULT_MAX_FPS) as f32).unwrap();
     }
 
+    DAY_LENGTH.set(args.day_length).unwrap();
+
+    // Before any world exists, as plugins and scripts register tiles
+    let tiles = TileRegistry::load(&args.assets.join(registry::FILE)).unwrap_or_else(|e| {
+        println!("{}, using the built in tiles", e);
+        TileRegistry::builtin()
+    });
+    registry::REGISTRY.set(tiles).ok();
+    // Spawn rules name tiles, so they are checked against the loaded ones
+    let rules = spawning::load(&args.assets.join(spawning::FILE)).unwrap_or_else(|e| {
+        println!("{}, using the built in spawn rules", e);
+        spawning::builtin()
+    });
+    spawning::RULES.set(rules).ok();
+    let config = PluginConfig::load(&args.plugins).unwrap_or_else(|e| {
+        println!("{}, using the default plugins", e);
+        PluginConfig::default()
+    });
+    let (content, errors) = plugin::load(plugin::available(), &config);
+    errors.iter().for_each(|e| println!("{}", e));
+    plugin::CONTENT.set(content).ok();
+    let scripts = ScriptHost::load(&args.scripts);
+
+    if args.headless {
+        headless::run(&args.saves, &args.world, &args.seed, args.script.as_deref(), &scripts, args.save);
+        return;
+    }
+
+    let atlas = match Atlas::load(&args.assets) {
+        Ok(atlas) => Some(atlas),
+        Err(e) => {
+            println!("{}, falling back to solid colors", e);
+            None
+        }
+    };
+    ATLAS.set(atlas).ok();
+
     let window = Window::new("Grid Test", WINDOW_SIZE, WINDOW_SIZE, WindowOptions {
+        resize: true,
+        scale_mode: ScaleMode::UpperLeft,
         ..WindowOptions::default()
     }).unwrap();
     let size = window.get_size();
@@ -88,15 +199,15 @@ fn main() {
         .load()
         .unwrap();
 
-    let mut game = Game::new(window, dt, font);
+    let mut game = Game::new(window, dt, font, args.saves, args.script, scripts);
     game_loop(&mut game);
 }
 
 
 fn game_loop(game: &mut Game) {
     'main_loop: loop {
-        // End the game when closed
-        if !game.window.is_open() {
+        // End the game when closed or quit from the menu
+        if !game.window.is_open() || game.should_quit() {
             return;
         }
         game.update();
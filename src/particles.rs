@@ -0,0 +1,178 @@
+use rand::Rng;
+use raqote::{Color, DrawOptions, DrawTarget, SolidSource, Source};
+use crate::coords::{self, ViewTransform};
+use crate::{EntityPosition, TilePosition};
+use crate::world::World;
+
+/// Most particles alive at once, new ones are dropped while the pool is full
+pub const MAX_PARTICLES: usize = 2000;
+
+const DEBRIS_COUNT: usize = 10;
+const DEBRIS_GRAVITY: f32 = 0.4;
+const DUST_COUNT: usize = 6;
+const RAIN_PER_TICK: usize = 4;
+const RAIN_SPEED: f32 = 8.0;
+
+/// A short lived coloured square. Particles are not entities, they never interact with anything but tiles.
+#[derive(Clone, Debug)]
+pub struct Particle {
+    pub pos: EntityPosition,
+    pub vel: (f32, f32),
+    pub color: Color,
+    /// Width and height in world pixels
+    pub size: f32,
+    pub ticks_left: u32,
+    /// Pulled down by this much every tick
+    pub gravity: f32,
+    /// Removed on touching a solid tile instead of coming to rest on it
+    pub dies_on_impact: bool,
+}
+
+/// Pooled particle storage. Dead particles are swapped past `live` and their slots reused, so a busy
+/// scene does not allocate once the pool has grown.
+pub struct Particles {
+    pool: Vec<Particle>,
+    live: usize,
+    cap: usize,
+}
+
+impl Particles {
+    pub fn new(cap: usize) -> Particles {
+        Particles {
+            pool: Vec::with_capacity(cap),
+            live: 0,
+            cap,
+        }
+    }
+
+    /// Number of live particles
+    pub fn len(&self) -> usize {
+        self.live
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.pool[..self.live].iter()
+    }
+
+    /// Adds a particle, returning false when the pool is full
+    pub fn spawn(&mut self, particle: Particle) -> bool {
+        if self.live >= self.cap {
+            return false;
+        }
+        if self.live < self.pool.len() {
+            self.pool[self.live] = particle;
+        } else {
+            self.pool.push(particle);
+        }
+        self.live += 1;
+        true
+    }
+
+    /// Moves every particle a tick, removing those that expired
+    pub fn update(&mut self, world: &World) {
+        let bounds = world.pixel_size();
+        let mut i = 0;
+        while i < self.live {
+            if !step(&mut self.pool[i], world, bounds) {
+                self.live -= 1;
+                self.pool.swap(i, self.live);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Bits of a broken tile flying out from its center
+    pub fn debris(&mut self, pos: &TilePosition, color: Color, rng: &mut impl Rng) {
+        let center = coords::tile_center(pos);
+        for _ in 0..DEBRIS_COUNT {
+            self.spawn(Particle {
+                pos: center.offset((rng.gen_range(-6.0..6.0), rng.gen_range(-6.0..6.0))),
+                vel: (rng.gen_range(-2.5..2.5), rng.gen_range(1.0..5.0)),
+                color,
+                size: rng.gen_range(2.0..4.0),
+                ticks_left: rng.gen_range(20..40),
+                gravity: DEBRIS_GRAVITY,
+                dies_on_impact: false,
+            });
+        }
+    }
+
+    /// A puff kicked up under something `width` pixels wide landing at `pos`
+    pub fn dust(&mut self, pos: &EntityPosition, width: f32, color: Color, rng: &mut impl Rng) {
+        // Nothing to spread the puff under, such as an entity without a collider
+        if width <= 0.0 {
+            return;
+        }
+        for i in 0..DUST_COUNT {
+            // Half the puff goes each way
+            let direction = if i % 2 == 0 { -1.0 } else { 1.0 };
+            self.spawn(Particle {
+                pos: pos.offset((width / 2.0 + direction * rng.gen_range(0.0..width / 2.0), 1.0)),
+                vel: (direction * rng.gen_range(0.5..1.5), rng.gen_range(0.2..0.8)),
+                color,
+                size: 2.0,
+                ticks_left: rng.gen_range(8..14),
+                gravity: 0.0,
+                dies_on_impact: false,
+            });
+        }
+    }
+
+    /// Raindrops starting at the top of the world
+    pub fn rain(&mut self, world_size: (f32, f32), rng: &mut impl Rng) {
+        for _ in 0..RAIN_PER_TICK {
+            self.spawn(Particle {
+                pos: EntityPosition(rng.gen_range(0.0..world_size.0), world_size.1 - 1.0),
+                vel: (-0.5, -RAIN_SPEED),
+                color: Color::new(0xb0, 0x8c, 0xa8, 0xe6),
+                size: 1.0,
+                ticks_left: (world_size.1 / RAIN_SPEED) as u32 + 1,
+                gravity: 0.0,
+                dies_on_impact: true,
+            });
+        }
+    }
+
+    pub fn render(&self, target: &mut DrawTarget, view: &ViewTransform) {
+        let (width, height) = (target.width() as f32, target.height() as f32);
+        for particle in self.iter() {
+            let (x, y, w, h) = view.world_rect_to_screen(&particle.pos, particle.size, particle.size);
+            if x + w < 0.0 || y + h < 0.0 || x > width || y > height {
+                continue;
+            }
+            // Raindrops are drawn as streaks
+            let h = if particle.dies_on_impact { h * 4.0 } else { h };
+            target.fill_rect(x, y, w, h, &Source::Solid(SolidSource::from(particle.color)), &DrawOptions::new());
+        }
+    }
+}
+
+impl Default for Particles {
+    fn default() -> Self {
+        Particles::new(MAX_PARTICLES)
+    }
+}
+
+/// Advances one particle, returning false once it should be removed
+fn step(particle: &mut Particle, world: &World, bounds: (f32, f32)) -> bool {
+    particle.ticks_left = particle.ticks_left.saturating_sub(1);
+    if particle.ticks_left == 0 {
+        return false;
+    }
+    particle.vel.1 -= particle.gravity;
+    let next = particle.pos.offset(particle.vel);
+    if next.0 < 0.0 || next.1 < 0.0 || next.0 >= bounds.0 || next.1 >= bounds.1 {
+        return false;
+    }
+    if world.is_occupied(&next.to_tile_coords()) {
+        if particle.dies_on_impact {
+            return false;
+        }
+        // Come to rest on top of the tile
+        particle.vel = (0.0, 0.0);
+        return true;
+    }
+    particle.pos = next;
+    true
+}
@@ -0,0 +1,248 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use raqote::{DrawOptions, DrawTarget, LineCap, LineJoin, PathBuilder, SolidSource, Source, StrokeStyle};
+use crate::coords::ViewTransform;
+use crate::TilePosition;
+use crate::world::World;
+
+/// Limits on how a walker can move between tiles
+#[derive(Clone, Debug)]
+pub struct PathOptions {
+    /// How many tiles up a single jump can reach
+    pub max_jump: usize,
+    /// How many tiles a walker is willing to fall
+    pub max_drop: usize,
+    /// How many nodes may be expanded before giving up
+    pub node_budget: usize,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        PathOptions {
+            max_jump: 1,
+            max_drop: 3,
+            node_budget: 2000,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct Node {
+    /// Cost so far plus the heuristic
+    estimate: usize,
+    cost: usize,
+    pos: TilePosition,
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the heap pops the cheapest node first
+        other.estimate.cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A cell can be stood in if it is free and the tile below it is solid
+pub fn is_walkable(world: &World, pos: &TilePosition) -> bool {
+    pos.1 > 0
+        && world.get_tile(pos).is_some()
+        && !world.is_occupied(pos)
+        && world.is_occupied(&TilePosition(pos.0, pos.1 - 1))
+}
+
+fn is_free(world: &World, x: usize, y: usize) -> bool {
+    let pos = TilePosition(x, y);
+    world.get_tile(&pos).is_some() && !world.is_occupied(&pos)
+}
+
+/// Cells reachable from a standing cell by walking, jumping up or dropping down one column over,
+/// along with the cost of the move
+fn neighbours(world: &World, pos: &TilePosition, options: &PathOptions) -> Vec<(TilePosition, usize)> {
+    let mut result = vec![];
+    for dx in [-1isize, 1] {
+        let x = pos.0 as isize + dx;
+        if x < 0 {
+            continue;
+        }
+        let x = x as usize;
+
+        let walk = TilePosition(x, pos.1);
+        if is_walkable(world, &walk) {
+            result.push((walk, 1));
+            continue;
+        }
+
+        if !is_free(world, x, pos.1) {
+            // Wall in the way, try to jump on top of it. Head room above us has to be clear.
+            for height in 1..=options.max_jump {
+                if !is_free(world, pos.0, pos.1 + height) {
+                    break;
+                }
+                let target = TilePosition(x, pos.1 + height);
+                if is_walkable(world, &target) {
+                    result.push((target, 1 + height));
+                    break;
+                }
+            }
+        } else {
+            // Open air next to us, fall down the column until we land
+            for depth in 1..=options.max_drop.min(pos.1) {
+                let target = TilePosition(x, pos.1 - depth);
+                if !is_free(world, x, target.1) {
+                    break;
+                }
+                if is_walkable(world, &target) {
+                    result.push((target, 1 + depth));
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+fn heuristic(a: &TilePosition, b: &TilePosition) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// Finds the cheapest walkable path from `start` to `goal`, both inclusive.
+/// Returns None if there is no path or the node budget runs out.
+pub fn find_path(world: &World, start: &TilePosition, goal: &TilePosition, options: &PathOptions) -> Option<Vec<TilePosition>> {
+    if !is_walkable(world, start) || !is_walkable(world, goal) {
+        return None;
+    }
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<TilePosition, TilePosition> = HashMap::new();
+    let mut costs: HashMap<TilePosition, usize> = HashMap::new();
+    costs.insert(start.clone(), 0);
+    open.push(Node { estimate: heuristic(start, goal), cost: 0, pos: start.clone() });
+
+    let mut expanded = 0;
+    while let Some(Node { cost, pos, .. }) = open.pop() {
+        if &pos == goal {
+            let mut path = vec![pos];
+            while let Some(prev) = came_from.get(path.last().unwrap()) {
+                path.push(prev.clone());
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if costs.get(&pos).is_some_and(|best| cost > *best) {
+            // Stale entry, a cheaper way here was already found
+            continue;
+        }
+        expanded += 1;
+        if expanded > options.node_budget {
+            return None;
+        }
+        for (next, step) in neighbours(world, &pos, options) {
+            let next_cost = cost + step;
+            if costs.get(&next).is_none_or(|best| next_cost < *best) {
+                costs.insert(next.clone(), next_cost);
+                came_from.insert(next.clone(), pos.clone());
+                open.push(Node { estimate: next_cost + heuristic(&next, goal), cost: next_cost, pos: next });
+            }
+        }
+    }
+    None
+}
+
+/// Draws a line through the centers of the path's tiles
+pub fn render_path(target: &mut DrawTarget, path: &[TilePosition], view: &ViewTransform) {
+    let center = |pos: &TilePosition| {
+        let (x, y, w, h) = view.tile_to_screen(pos);
+        (x + w / 2.0, y + h / 2.0)
+    };
+    let source = Source::Solid(SolidSource::from_unpremultiplied_argb(0xc0, 0x20, 0x60, 0xff));
+    let mut pb = PathBuilder::new();
+    for (i, pos) in path.iter().enumerate() {
+        let (x, y) = center(pos);
+        if i == 0 {
+            pb.move_to(x, y);
+        } else {
+            pb.line_to(x, y);
+        }
+        target.fill_rect(x - 3.0, y - 3.0, 6.0, 6.0, &source, &DrawOptions::new());
+    }
+    let style = StrokeStyle {
+        width: 2.0,
+        cap: LineCap::Round,
+        join: LineJoin::Round,
+        ..StrokeStyle::default()
+    };
+    target.stroke(&pb.finish(), &source, &style, &DrawOptions::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::tile::base::BaseTile;
+    use super::*;
+
+    /// A world with a bedrock floor and stone wherever `stone` says, standing cells being at y >= 1
+    fn world_with(stone: impl Fn(usize, usize) -> bool) -> Rc<RefCell<World>> {
+        World::build(12, 8, 1, |world| {
+            for x in 0..12 {
+                world.set_tile(&TilePosition(x, 0), BaseTile::named("bedrock").unwrap());
+                for y in (1..8).filter(|&y| stone(x, y)) {
+                    world.set_tile(&TilePosition(x, y), BaseTile::named("stone").unwrap());
+                }
+            }
+        })
+    }
+
+    fn options(max_jump: usize, max_drop: usize, node_budget: usize) -> PathOptions {
+        PathOptions { max_jump, max_drop, node_budget }
+    }
+
+    #[test]
+    fn walks_along_flat_ground() {
+        let world = world_with(|_, _| false);
+        let path = find_path(&world.borrow(), &TilePosition(1, 1), &TilePosition(6, 1), &PathOptions::default()).unwrap();
+        assert_eq!(path, (1..=6).map(|x| TilePosition(x, 1)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn jumps_no_higher_than_max_jump() {
+        // A two tile high step to climb over
+        let world = world_with(|x, y| x == 5 && y <= 2);
+        let (start, goal) = (TilePosition(2, 1), TilePosition(8, 1));
+        assert_eq!(find_path(&world.borrow(), &start, &goal, &options(1, 3, 2000)), None);
+        let path = find_path(&world.borrow(), &start, &goal, &options(2, 3, 2000)).unwrap();
+        assert!(path.contains(&TilePosition(5, 3)));
+        assert_eq!(path.last(), Some(&goal));
+    }
+
+    #[test]
+    fn drops_no_further_than_max_drop() {
+        // A ledge three tiles above the floor on the left
+        let world = world_with(|x, y| x <= 4 && y <= 3);
+        let (start, goal) = (TilePosition(1, 4), TilePosition(8, 1));
+        assert_eq!(find_path(&world.borrow(), &start, &goal, &options(1, 2, 2000)), None);
+        let path = find_path(&world.borrow(), &start, &goal, &options(1, 3, 2000)).unwrap();
+        assert!(path.windows(2).any(|step| step[0] == TilePosition(4, 4) && step[1] == TilePosition(5, 1)));
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_path() {
+        let world = world_with(|x, _| x == 6);
+        assert_eq!(find_path(&world.borrow(), &TilePosition(2, 1), &TilePosition(9, 1), &PathOptions::default()), None);
+        // Cells that can't be stood in aren't goals either
+        assert_eq!(find_path(&world.borrow(), &TilePosition(2, 1), &TilePosition(3, 4), &PathOptions::default()), None);
+    }
+
+    #[test]
+    fn gives_up_when_the_budget_runs_out() {
+        let world = world_with(|_, _| false);
+        let (start, goal) = (TilePosition(1, 1), TilePosition(10, 1));
+        assert_eq!(find_path(&world.borrow(), &start, &goal, &options(1, 3, 5)), None);
+        assert!(find_path(&world.borrow(), &start, &goal, &options(1, 3, 10)).is_some());
+    }
+}
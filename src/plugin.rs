@@ -0,0 +1,324 @@
+pub mod ores;
+
+use std::any::TypeId;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::OnceLock;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use crate::console::{commands, Arguments, Command, CommandContext, CommandRegistry, CommandResult};
+use crate::ecs::EntityId;
+use crate::tile::Tile;
+use crate::world::World;
+use crate::EntityPosition;
+
+// Plugins are compiled in and listed in `available`. At startup every enabled plugin registers its
+// content under its own namespace, so a plugin "ores" adds tiles such as "ores:copper_ore".
+
+/// What the enabled plugins registered, set once at startup
+pub static CONTENT: OnceLock<Content> = OnceLock::new();
+
+/// Every plugin that can be enabled
+pub fn available() -> Vec<Box<dyn Plugin>> {
+    vec![Box::new(ores::OresPlugin)]
+}
+
+pub trait Plugin {
+    /// Namespace of everything the plugin registers. Lowercase letters, digits and underscores.
+    fn id(&self) -> &'static str;
+    fn version(&self) -> &'static str;
+    /// Plugins that have to load first
+    fn dependencies(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Whether the plugin loads when the config doesn't mention it
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+    fn register(&self, registrar: &mut Registrar) -> Result<(), PluginError>;
+}
+
+pub type TileFactory = fn() -> Box<dyn Tile>;
+pub type EntityFactory = fn(&mut World, EntityPosition) -> EntityId;
+/// Runs after the base terrain is generated, with a generator seeded from the world seed and the pass id
+pub type GenerationPass = fn(&mut World, &mut StdRng);
+pub type CommandHandler = fn(&mut CommandContext, &Arguments) -> CommandResult;
+
+#[derive(Clone, Debug)]
+pub struct Recipe {
+    pub id: String,
+    /// Ids of what is used up and how many of each
+    pub inputs: Vec<(String, u32)>,
+    pub output: (String, u32),
+}
+
+#[derive(Clone)]
+struct PluginCommand {
+    name: String,
+    usage: String,
+    help: String,
+    arity: RangeInclusive<usize>,
+    run: CommandHandler,
+}
+
+#[derive(Debug)]
+pub enum PluginError {
+    Io(std::io::Error),
+    Config(toml::de::Error),
+    /// An id that isn't lowercase letters, digits and underscores
+    InvalidId(String),
+    /// Something of `kind` was already registered as `id`
+    Conflict { kind: &'static str, id: String },
+    MissingDependency { plugin: String, dependency: String },
+    Failed { plugin: String, reason: String },
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginError::Io(e) => write!(f, "could not read plugin config: {}", e),
+            PluginError::Config(e) => write!(f, "invalid plugin config: {}", e),
+            PluginError::InvalidId(id) => write!(f, "invalid id '{}', use lowercase letters, digits and _", id),
+            PluginError::Conflict { kind, id } => write!(f, "{} '{}' is already registered", kind, id),
+            PluginError::MissingDependency { plugin, dependency } => write!(f, "plugin {} needs {}, which is not enabled", plugin, dependency),
+            PluginError::Failed { plugin, reason } => write!(f, "plugin {} failed to load: {}", plugin, reason),
+        }
+    }
+}
+
+impl From<std::io::Error> for PluginError {
+    fn from(e: std::io::Error) -> Self {
+        PluginError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for PluginError {
+    fn from(e: toml::de::Error) -> Self {
+        PluginError::Config(e)
+    }
+}
+
+/// Which plugins to load, such as `[enabled]` with `ores = false`
+#[derive(Deserialize, Default)]
+pub struct PluginConfig {
+    #[serde(default)]
+    pub enabled: BTreeMap<String, bool>,
+}
+
+impl PluginConfig {
+    /// Reads the config at `path`, a missing file leaving every plugin at its default
+    pub fn load(path: &Path) -> Result<PluginConfig, PluginError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(toml::from_str(&text)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(PluginConfig::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn is_enabled(&self, plugin: &dyn Plugin) -> bool {
+        self.enabled.get(plugin.id()).copied().unwrap_or(plugin.enabled_by_default())
+    }
+}
+
+#[derive(Default)]
+pub struct Content {
+    /// Ids and versions of the loaded plugins, in load order
+    plugins: Vec<(String, String)>,
+    /// Factories by id, with the type they create so tiles can be named
+    tiles: BTreeMap<String, (TileFactory, TypeId)>,
+    entities: BTreeMap<String, EntityFactory>,
+    passes: Vec<(String, GenerationPass)>,
+    recipes: BTreeMap<String, Recipe>,
+    commands: Vec<PluginCommand>,
+}
+
+impl Content {
+    fn merge(&mut self, other: Content) {
+        self.plugins.extend(other.plugins);
+        self.tiles.extend(other.tiles);
+        self.entities.extend(other.entities);
+        self.passes.extend(other.passes);
+        self.recipes.extend(other.recipes);
+        self.commands.extend(other.commands);
+    }
+}
+
+/// What a plugin registers through. Names are namespaced with the plugin's id and checked for
+/// conflicts; nothing a plugin registered is kept if it fails to load.
+pub struct Registrar<'a> {
+    namespace: &'static str,
+    /// What earlier plugins registered
+    loaded: &'a Content,
+    /// Names of the built in commands
+    builtin_commands: &'a BTreeSet<String>,
+    added: Content,
+}
+
+impl<'a> Registrar<'a> {
+    fn namespaced(&self, name: &str) -> Result<String, PluginError> {
+        if !is_valid_id(name) {
+            return Err(PluginError::InvalidId(name.to_string()));
+        }
+        Ok(format!("{}:{}", self.namespace, name))
+    }
+
+    pub fn tile(&mut self, name: &str, factory: TileFactory) -> Result<(), PluginError> {
+        let id = self.namespaced(name)?;
+        let type_id = factory().as_any().type_id();
+        let mut registered = self.loaded.tiles.iter().chain(self.added.tiles.iter());
+        if let Some((other, _)) = registered.find(|(other, (_, other_type))| **other == id || *other_type == type_id) {
+            return Err(PluginError::Conflict { kind: "tile", id: other.clone() });
+        }
+        self.added.tiles.insert(id, (factory, type_id));
+        Ok(())
+    }
+
+    pub fn entity(&mut self, name: &str, factory: EntityFactory) -> Result<(), PluginError> {
+        let id = self.namespaced(name)?;
+        if self.added.entities.contains_key(&id) {
+            return Err(PluginError::Conflict { kind: "entity", id });
+        }
+        self.added.entities.insert(id, factory);
+        Ok(())
+    }
+
+    pub fn generation_pass(&mut self, name: &str, pass: GenerationPass) -> Result<(), PluginError> {
+        let id = self.namespaced(name)?;
+        if self.added.passes.iter().any(|(other, _)| *other == id) {
+            return Err(PluginError::Conflict { kind: "generation pass", id });
+        }
+        self.added.passes.push((id, pass));
+        Ok(())
+    }
+
+    /// Adds a recipe, its inputs and output being full ids such as "stone" or "ores:copper_ore"
+    pub fn recipe(&mut self, name: &str, inputs: &[(&str, u32)], output: (&str, u32)) -> Result<(), PluginError> {
+        let id = self.namespaced(name)?;
+        if self.added.recipes.contains_key(&id) {
+            return Err(PluginError::Conflict { kind: "recipe", id });
+        }
+        let recipe = Recipe {
+            id: id.clone(),
+            inputs: inputs.iter().map(|(input, count)| (input.to_string(), *count)).collect(),
+            output: (output.0.to_string(), output.1),
+        };
+        self.added.recipes.insert(id, recipe);
+        Ok(())
+    }
+
+    /// Adds a console command. Commands are typed without a namespace, so the name has to be free.
+    pub fn command(&mut self, name: &str, usage: &str, help: &str, arity: RangeInclusive<usize>, run: CommandHandler) -> Result<(), PluginError> {
+        if !is_valid_id(name) {
+            return Err(PluginError::InvalidId(name.to_string()));
+        }
+        let taken = self.builtin_commands.contains(name)
+            || self.loaded.commands.iter().chain(self.added.commands.iter()).any(|command| command.name == name);
+        if taken {
+            return Err(PluginError::Conflict { kind: "command", id: name.to_string() });
+        }
+        self.added.commands.push(PluginCommand { name: name.to_string(), usage: usage.to_string(), help: help.to_string(), arity, run });
+        Ok(())
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Registers the enabled plugins of `plugins`. Plugins load in id order, each after its dependencies,
+/// so the same set always loads the same way. Returns the content of the plugins that loaded and
+/// why the others didn't.
+pub fn load(plugins: Vec<Box<dyn Plugin>>, config: &PluginConfig) -> (Content, Vec<PluginError>) {
+    let mut content = Content::default();
+    let mut errors = vec![];
+    let mut builtin = CommandRegistry::default();
+    commands::register(&mut builtin);
+    let builtin_commands: BTreeSet<String> = builtin.iter().map(|command| command.name.clone()).collect();
+    let mut pending: BTreeMap<&'static str, Box<dyn Plugin>> = BTreeMap::new();
+    for plugin in plugins.into_iter().filter(|plugin| config.is_enabled(plugin.as_ref())) {
+        if !is_valid_id(plugin.id()) {
+            errors.push(PluginError::InvalidId(plugin.id().to_string()));
+        } else if pending.contains_key(plugin.id()) {
+            errors.push(PluginError::Conflict { kind: "plugin", id: plugin.id().to_string() });
+        } else {
+            pending.insert(plugin.id(), plugin);
+        }
+    }
+    loop {
+        let is_loaded = |id: &&str| content.plugins.iter().any(|(loaded, _)| loaded == id);
+        let next = pending.iter()
+            .find(|(_, plugin)| plugin.dependencies().iter().all(is_loaded))
+            .map(|(id, _)| *id);
+        let Some(plugin) = next.and_then(|id| pending.remove(id)) else {
+            break;
+        };
+        let mut registrar = Registrar { namespace: plugin.id(), loaded: &content, builtin_commands: &builtin_commands, added: Content::default() };
+        match plugin.register(&mut registrar) {
+            Ok(()) => {
+                let mut added = registrar.added;
+                added.plugins.push((plugin.id().to_string(), plugin.version().to_string()));
+                content.merge(added);
+            }
+            Err(e) => errors.push(PluginError::Failed { plugin: plugin.id().to_string(), reason: e.to_string() }),
+        }
+    }
+    // Whatever is left waits on a plugin that is disabled or failed
+    for plugin in pending.values() {
+        let dependency = plugin.dependencies().iter()
+            .find(|dependency| !content.plugins.iter().any(|(loaded, _)| loaded == *dependency))
+            .map_or(String::new(), |dependency| dependency.to_string());
+        errors.push(PluginError::MissingDependency { plugin: plugin.id().to_string(), dependency });
+    }
+    (content, errors)
+}
+
+fn content() -> &'static Content {
+    CONTENT.get_or_init(Content::default)
+}
+
+/// Loaded plugins as "id@version", in load order
+pub fn load_order() -> Vec<String> {
+    content().plugins.iter().map(|(id, version)| format!("{}@{}", id, version)).collect()
+}
+
+pub fn new_tile(id: &str) -> Option<Box<dyn Tile>> {
+    content().tiles.get(id).map(|(factory, _)| factory())
+}
+
+/// The id a plugin registered the tile's type as
+pub fn tile_id(tile: &dyn Tile) -> Option<&'static str> {
+    let type_id = tile.as_any().type_id();
+    content().tiles.iter().find(|(_, (_, other))| *other == type_id).map(|(id, _)| id.as_str())
+}
+
+pub fn spawn_entity(world: &mut World, id: &str, pos: EntityPosition) -> Option<EntityId> {
+    content().entities.get(id).map(|factory| factory(world, pos))
+}
+
+/// Runs every generation pass over a freshly generated world
+pub fn generate(world: &mut World) {
+    for (id, pass) in &content().passes {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        let mut rng = StdRng::seed_from_u64(world.seed() ^ hasher.finish());
+        pass(world, &mut rng);
+    }
+}
+
+pub fn recipes() -> impl Iterator<Item = &'static Recipe> {
+    content().recipes.values()
+}
+
+/// Adds the plugins' commands
+pub fn register_commands(registry: &mut CommandRegistry) {
+    for command in &content().commands {
+        registry.register(Command::new(&command.name, &command.usage, &command.help, command.arity.clone(), command.run));
+    }
+}
@@ -0,0 +1,86 @@
+use std::any::Any;
+use font_kit::font::Font;
+use rand::rngs::StdRng;
+use rand::Rng;
+use raqote::{Color, DrawOptions, DrawTarget, SolidSource, Source};
+use crate::console::{Arguments, CommandContext, CommandResult};
+use crate::coords::ViewTransform;
+use crate::entity::slime::SlimeEntity;
+use crate::plugin::{Plugin, PluginError, Registrar};
+use crate::tile::base::BaseTile;
+use crate::tile::registry;
+use crate::tile::{self, Tile, TileType};
+use crate::world::World;
+use crate::TilePosition;
+
+/// Chance of a stone tile turning into copper ore
+const COPPER_CHANCE: f64 = 0.06;
+
+/// Adds copper ore to the stone layer
+pub struct OresPlugin;
+
+impl Plugin for OresPlugin {
+    fn id(&self) -> &'static str {
+        "ores"
+    }
+
+    fn version(&self) -> &'static str {
+        "1.0"
+    }
+
+    fn register(&self, registrar: &mut Registrar) -> Result<(), PluginError> {
+        registrar.tile("copper_ore", || Box::new(CopperOreTile))?;
+        registrar.entity("ore_slime", |world, pos| world.spawn(SlimeEntity::new(pos)))?;
+        registrar.generation_pass("copper_veins", copper_veins)?;
+        registrar.recipe("copper_ingot", &[("ores:copper_ore", 2)], ("ores:copper_ingot", 1))?;
+        registrar.command("ores", "ores", "Counts the copper ore in the world", 0..=0, count_ores)?;
+        Ok(())
+    }
+}
+
+pub struct CopperOreTile;
+
+impl Tile for CopperOreTile {
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, _font: &Font, view: &ViewTransform, _tick: u64) {
+        let (x, y, w, h) = view.tile_to_screen(pos);
+        target.fill_rect(x, y, w, h, &Source::Solid(SolidSource::from(registry::required("stone").color())), &DrawOptions::new());
+        // A fleck of copper in the middle of the stone
+        let fleck = Source::Solid(SolidSource::from(Color::new(255, 196, 110, 60)));
+        target.fill_rect(x + w * 0.3, y + h * 0.3, w * 0.4, h * 0.4, &fleck, &DrawOptions::new());
+    }
+
+    fn get_type(&self) -> &TileType {
+        &TileType::Plugin
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn copper_veins(world: &mut World, rng: &mut StdRng) {
+    for y in 0..world.height() {
+        for x in 0..world.width() {
+            let pos = TilePosition(x, y);
+            let is_stone = world.get_tile(&pos)
+                .and_then(|tile| tile.as_any().downcast_ref::<BaseTile>())
+                .is_some_and(|tile| tile.def().id == "stone");
+            if is_stone && rng.gen_bool(COPPER_CHANCE) {
+                world.set_tile(&pos, Box::new(CopperOreTile));
+            }
+        }
+    }
+}
+
+fn count_ores(ctx: &mut CommandContext, _args: &Arguments) -> CommandResult {
+    let world = ctx.world.borrow();
+    let count = (0..world.height())
+        .flat_map(|y| (0..world.width()).map(move |x| TilePosition(x, y)))
+        .filter(|pos| world.get_tile(pos).and_then(|t| tile::name_of(t.as_ref())).is_some_and(|name| name == "ores:copper_ore"))
+        .count();
+    Ok(format!("{} copper ore", count))
+}
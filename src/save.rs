@@ -0,0 +1,325 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use crate::ecs::component::{Health, Position};
+use crate::ecs::EntityId;
+use crate::entity::player::PlayerEntity;
+use crate::plugin;
+use crate::tile;
+use crate::tile::entity::{TileEntity, CHEST_SLOTS, MAX_STACK};
+use crate::tile::meta::TileMeta;
+use crate::world::World;
+use crate::{EntityPosition, TilePosition};
+
+/// Bumped whenever the save format changes in a way older saves can't be read
+pub const SAVE_VERSION: u32 = 1;
+const EXTENSION: &str = "toml";
+
+/// A world as written to disk. Tiles without a name, such as the player's, are saved as air.
+#[derive(Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    /// The u64 seed reinterpreted as i64, TOML integers being signed
+    pub seed: i64,
+    pub width: usize,
+    pub height: usize,
+    pub time: u64,
+    pub day_length: u64,
+    #[serde(default)]
+    pub raining: bool,
+    /// Tile names, `tiles` refers to them by index + 1. Scripted tiles need their script to load.
+    pub palette: Vec<String>,
+    /// One row per y starting at the bottom, 0 being air. Wide enough for a palette of every tile
+    /// plugins and scripts can add, saves from when indices were a byte still load.
+    pub tiles: Vec<Vec<u16>>,
+    pub player: Option<PlayerSave>,
+    /// Plugins loaded when the world was saved, as "id@version" in load order
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// Metadata of the tiles whose metadata isn't the default
+    #[serde(default)]
+    pub meta: Vec<MetaSave>,
+    #[serde(default)]
+    pub tile_entities: Vec<TileEntitySave>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MetaSave {
+    pub x: usize,
+    pub y: usize,
+    #[serde(flatten)]
+    pub meta: TileMeta,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TileEntitySave {
+    pub x: usize,
+    pub y: usize,
+    #[serde(flatten)]
+    pub entity: TileEntity,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerSave {
+    pub x: f32,
+    pub y: f32,
+    pub health: u8,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Write(toml::ser::Error),
+    Version(u32),
+    UnknownTile(String),
+    Corrupt(String),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access save: {}", e),
+            SaveError::Parse(e) => write!(f, "invalid save file: {}", e),
+            SaveError::Write(e) => write!(f, "could not write save: {}", e),
+            SaveError::Version(version) => write!(f, "save version {} is not supported, expected {}", version, SAVE_VERSION),
+            SaveError::UnknownTile(name) => write!(f, "unknown tile '{}' in save, is a plugin or script missing?", name),
+            SaveError::Corrupt(reason) => write!(f, "corrupt save: {}", reason),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for SaveError {
+    fn from(e: toml::de::Error) -> Self {
+        SaveError::Parse(e)
+    }
+}
+
+impl From<toml::ser::Error> for SaveError {
+    fn from(e: toml::ser::Error) -> Self {
+        SaveError::Write(e)
+    }
+}
+
+/// The file a world called `name` is saved to
+pub fn path_for(dir: &Path, name: &str) -> PathBuf {
+    let name: String = name.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect();
+    dir.join(format!("{}.{}", if name.is_empty() { "world" } else { &name }, EXTENSION))
+}
+
+/// Names of the saves in `dir`, sorted. A missing directory has no saves.
+pub fn list(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+impl SaveData {
+    pub fn from_world(world: &World, player: EntityId) -> SaveData {
+        let mut palette: Vec<String> = vec![];
+        let tiles = (0..world.height()).map(|y| {
+            (0..world.width()).map(|x| {
+                let name = world.get_tile(&TilePosition(x, y))
+                    .and_then(|tile| tile::name_of(tile.as_ref()))
+                    .filter(|name| name != "air");
+                match name {
+                    Some(name) => match palette.iter().position(|n| *n == name) {
+                        Some(i) => i as u16 + 1,
+                        None => {
+                            palette.push(name);
+                            palette.len() as u16
+                        }
+                    },
+                    None => 0,
+                }
+            }).collect()
+        }).collect();
+        let meta = (0..world.height())
+            .flat_map(|y| (0..world.width()).map(move |x| (x, y)))
+            .map(|(x, y)| MetaSave { x, y, meta: world.meta(&TilePosition(x, y)) })
+            .filter(|saved| !saved.meta.is_default())
+            .collect();
+        let mut tile_entities: Vec<TileEntitySave> = world.tile_entities()
+            .map(|(pos, entity)| TileEntitySave { x: pos.0, y: pos.1, entity: entity.clone() })
+            .collect();
+        tile_entities.sort_by_key(|saved| (saved.y, saved.x));
+        let ecs = world.ecs();
+        let player = match (ecs.get::<Position>(player), ecs.get::<Health>(player)) {
+            (Some(pos), Some(health)) => Some(PlayerSave { x: pos.0.0, y: pos.0.1, health: health.current }),
+            _ => None,
+        };
+        SaveData {
+            version: SAVE_VERSION,
+            seed: world.seed() as i64,
+            width: world.width(),
+            height: world.height(),
+            time: world.clock().time(),
+            day_length: world.clock().day_length(),
+            raining: world.raining,
+            palette,
+            tiles,
+            player,
+            plugins: plugin::load_order(),
+            meta,
+            tile_entities,
+        }
+    }
+
+    /// Builds the saved world, spawning the player at `default_spawn` if none was saved
+    pub fn into_world(self, default_spawn: EntityPosition) -> Result<(Rc<RefCell<World>>, EntityId), SaveError> {
+        if self.version != SAVE_VERSION {
+            return Err(SaveError::Version(self.version));
+        }
+        if let Some(name) = self.palette.iter().find(|name| tile::from_name(name).is_none()) {
+            return Err(SaveError::UnknownTile(name.clone()));
+        }
+        let palette = self.palette;
+        if self.width == 0 || self.height == 0 {
+            return Err(SaveError::Corrupt(format!("the world is {}x{}", self.width, self.height)));
+        }
+        if self.tiles.len() != self.height || self.tiles.iter().any(|row| row.len() != self.width) {
+            return Err(SaveError::Corrupt(format!("tiles are not {}x{}", self.width, self.height)));
+        }
+        if let Some(index) = self.tiles.iter().flatten().find(|&&i| i as usize > palette.len()) {
+            return Err(SaveError::Corrupt(format!("tile {} is not in the palette", index)));
+        }
+        let in_world = |x: usize, y: usize| x < self.width && y < self.height;
+        if let Some(saved) = self.meta.iter().find(|saved| !in_world(saved.x, saved.y)) {
+            return Err(SaveError::Corrupt(format!("metadata at ({},{}) is outside of the world", saved.x, saved.y)));
+        }
+        if let Some(saved) = self.tile_entities.iter().find(|saved| !in_world(saved.x, saved.y)) {
+            return Err(SaveError::Corrupt(format!("tile entity at ({},{}) is outside of the world", saved.x, saved.y)));
+        }
+        if let Some(saved) = self.player.as_ref().filter(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            return Err(SaveError::Corrupt(format!("the player is at ({},{})", saved.x, saved.y)));
+        }
+        for saved in &self.tile_entities {
+            if let TileEntity::Chest(chest) = &saved.entity {
+                if chest.items.len() > CHEST_SLOTS {
+                    return Err(SaveError::Corrupt(format!("chest at ({},{}) holds more than {} stacks", saved.x, saved.y, CHEST_SLOTS)));
+                }
+            }
+            if let Some(stack) = saved.entity.stacks().into_iter().find(|stack| stack.count == 0 || stack.count > MAX_STACK) {
+                return Err(SaveError::Corrupt(format!("stack of {} at ({},{}) is not between 1 and {}", stack, saved.x, saved.y, MAX_STACK)));
+            }
+        }
+        let tiles = self.tiles;
+        let world = World::build(self.width, self.height, self.seed as u64, |world| {
+            for (y, row) in tiles.iter().enumerate() {
+                for (x, &index) in row.iter().enumerate() {
+                    if index > 0 {
+                        world.set_tile(&TilePosition(x, y), tile::from_name(&palette[index as usize - 1]).unwrap());
+                    }
+                }
+            }
+        });
+        let mut w = world.borrow_mut();
+        for saved in self.meta {
+            w.set_meta(&TilePosition(saved.x, saved.y), saved.meta);
+        }
+        for saved in self.tile_entities {
+            let pos = TilePosition(saved.x, saved.y);
+            // Placing the tiles made empty tile entities of the kinds their definitions ask for
+            match w.tile_entity_mut(&pos) {
+                Some(entity) if entity.kind() == saved.entity.kind() => *entity = saved.entity,
+                _ => println!("dropping the saved {} at {}, its tile no longer has one", saved.entity.kind(), pos),
+            }
+        }
+        w.clock_mut().set_day_length(self.day_length);
+        w.clock_mut().set_time(self.time);
+        w.raining = self.raining;
+        let mut bundle = PlayerEntity::new(Some(self.player.as_ref().map_or(default_spawn, |p| EntityPosition(p.x, p.y))));
+        if let Some(saved) = &self.player {
+            bundle.health.current = saved.health.min(bundle.health.max);
+        }
+        let player = w.spawn(bundle);
+        drop(w);
+        Ok((world, player))
+    }
+}
+
+/// Writes the world to `path`, creating its directory if needed
+pub fn save(world: &World, player: EntityId, path: &Path) -> Result<(), SaveError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let data = toml::to_string(&SaveData::from_world(world, player))?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+pub fn load(path: &Path, default_spawn: EntityPosition) -> Result<(Rc<RefCell<World>>, EntityId), SaveError> {
+    let data: SaveData = toml::from_str(&fs::read_to_string(path)?)?;
+    let plugins = plugin::load_order();
+    if data.plugins != plugins {
+        println!("{} was saved with plugins [{}], loading it with [{}]", path.display(), data.plugins.join(", "), plugins.join(", "));
+    }
+    data.into_world(default_spawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save_data(width: usize, height: usize) -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            seed: 1,
+            width,
+            height,
+            time: 0,
+            day_length: 1000,
+            raining: false,
+            palette: vec!["stone".to_string()],
+            tiles: vec![vec![1; width]; height],
+            player: None,
+            plugins: vec![],
+            meta: vec![],
+            tile_entities: vec![],
+        }
+    }
+
+    fn is_corrupt(result: Result<(Rc<RefCell<World>>, EntityId), SaveError>) -> bool {
+        matches!(result, Err(SaveError::Corrupt(_)))
+    }
+
+    #[test]
+    fn loads_a_valid_save() {
+        let (world, _) = save_data(4, 3).into_world(EntityPosition(10.0, 10.0)).unwrap();
+        let name = world.borrow().get_tile(&TilePosition(3, 2)).and_then(|tile| tile::name_of(tile.as_ref()));
+        assert_eq!(name.as_deref(), Some("stone"));
+    }
+
+    #[test]
+    fn rejects_an_empty_world() {
+        assert!(is_corrupt(save_data(0, 3).into_world(EntityPosition(10.0, 10.0))));
+        assert!(is_corrupt(save_data(4, 0).into_world(EntityPosition(10.0, 10.0))));
+    }
+
+    #[test]
+    fn rejects_a_non_finite_player_position() {
+        for (x, y) in [(f32::NAN, 10.0), (10.0, f32::INFINITY), (f32::NEG_INFINITY, 10.0)] {
+            let mut data = save_data(4, 3);
+            data.player = Some(PlayerSave { x, y, health: 10 });
+            assert!(is_corrupt(data.into_world(EntityPosition(10.0, 10.0))));
+        }
+    }
+}
@@ -0,0 +1,415 @@
+pub mod api;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use raqote::Color;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, FuncArgs, Map, AST};
+use rhai::module_resolvers::DummyModuleResolver;
+use strum_macros::{Display, EnumString};
+use crate::console::{Arguments, Command, CommandContext, CommandError, CommandRegistry, CommandResult};
+use crate::ecs::component::ScriptBehaviour;
+use crate::ecs::EntityId;
+use crate::plugin;
+use crate::script::api::WorldHandle;
+use crate::tile::scripted::{self, ScriptTileKind, ScriptedTile};
+use crate::world::{World, WorldEvent};
+use crate::TilePosition;
+
+// Scripts are Rhai files in the scripts directory. Running a script registers its tile kinds,
+// behaviours, commands and event handlers, which the game then calls as hooks. Hooks only run
+// while the world is not borrowed and share a time budget each tick.
+
+/// Time all hooks together may take per tick, or per command or interaction
+pub const TICK_BUDGET: Duration = Duration::from_millis(4);
+/// Time a script may take to run when it is loaded
+const LOAD_BUDGET: Duration = Duration::from_millis(200);
+const EXTENSION: &str = "rhai";
+
+/// World events scripts can listen to with `on(event, fn)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum EventKind {
+    /// After every tick, called with the world
+    Tick,
+    /// Called with the world, x and y
+    TileChanged,
+    /// Called with the world and the entity
+    EntitySpawned,
+}
+
+/// A function of a script
+#[derive(Clone)]
+struct Hook {
+    script: usize,
+    function: FnPtr,
+}
+
+#[derive(Clone, Default)]
+struct TileHooks {
+    /// Called with the world, x and y on the tile's scheduled ticks and when a neighbouring tile changes
+    update: Option<Hook>,
+    /// Called with the world, x and y when the player interacts with the tile
+    interact: Option<Hook>,
+}
+
+#[derive(Clone)]
+struct ScriptCommand {
+    name: String,
+    usage: String,
+    help: String,
+    /// Called with the world and an array of the arguments
+    hook: Hook,
+}
+
+/// What scripts registered while they were loading
+#[derive(Default)]
+struct Registrations {
+    tiles: Vec<(ScriptTileKind, TileHooks)>,
+    behaviours: HashMap<String, Hook>,
+    commands: Vec<ScriptCommand>,
+    events: HashMap<EventKind, Vec<Hook>>,
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+}
+
+pub struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<Script>,
+    tiles: HashMap<String, TileHooks>,
+    behaviours: HashMap<String, Hook>,
+    commands: Vec<ScriptCommand>,
+    events: HashMap<EventKind, Vec<Hook>>,
+    /// When running scripts get stopped, None outside of a budgeted call
+    deadline: Rc<Cell<Option<Instant>>>,
+    /// Set once a hook ran over the budget, skipping the rest of the hooks until the next budget
+    out_of_budget: Cell<bool>,
+    /// What scripts printed and the errors they ran into, for the console
+    output: Rc<RefCell<Vec<String>>>,
+}
+
+impl ScriptHost {
+    /// A host without any scripts
+    pub fn empty() -> ScriptHost {
+        let deadline = Rc::new(Cell::new(None));
+        let output = Rc::new(RefCell::new(vec![]));
+        ScriptHost {
+            engine: sandboxed_engine(&deadline, &output),
+            scripts: vec![],
+            tiles: HashMap::new(),
+            behaviours: HashMap::new(),
+            commands: vec![],
+            events: HashMap::new(),
+            deadline,
+            out_of_budget: Cell::new(false),
+            output,
+        }
+    }
+
+    /// Runs every script in `dir` in name order. Scripts that fail to compile are skipped, the errors
+    /// are left in the output. The tile kinds they register become placeable, so this is done once.
+    pub fn load(dir: &Path) -> ScriptHost {
+        let mut host = ScriptHost::empty();
+        let registrations = Rc::new(RefCell::new(Registrations::default()));
+        let current = Rc::new(RefCell::new((0, String::new())));
+        register_loaders(&mut host.engine, &registrations, &current);
+        for path in script_paths(dir) {
+            let name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+            let ast = match host.engine.compile_file(path.clone()) {
+                Ok(ast) => ast,
+                Err(e) => {
+                    host.print(format!("could not load {}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            *current.borrow_mut() = (host.scripts.len(), name.clone());
+            host.deadline.set(Some(Instant::now() + LOAD_BUDGET));
+            if let Err(e) = host.engine.run_ast(&ast) {
+                host.print(format!("{}: {}", name, e));
+            }
+            host.deadline.set(None);
+            host.scripts.push(Script { name, ast });
+        }
+        let registrations = registrations.take();
+        let mut kinds = vec![];
+        for (kind, hooks) in registrations.tiles {
+            host.tiles.insert(kind.id.clone(), hooks);
+            kinds.push(kind);
+        }
+        scripted::KINDS.set(kinds).ok();
+        host.behaviours = registrations.behaviours;
+        host.commands = registrations.commands;
+        host.events = registrations.events;
+        if !host.scripts.is_empty() {
+            host.print(format!("loaded {} scripts", host.scripts.len()));
+        }
+        host
+    }
+
+    /// Takes what scripts printed since the last call
+    pub fn take_output(&self) -> Vec<String> {
+        self.output.take()
+    }
+
+    fn print(&self, line: String) {
+        self.output.borrow_mut().push(line);
+    }
+
+    /// Adds the scripts' commands, skipping any whose name is taken
+    pub fn register_commands(&self, registry: &mut CommandRegistry) {
+        for (index, command) in self.commands.iter().enumerate() {
+            let run = move |ctx: &mut CommandContext, args: &Arguments| ctx.scripts.run_command(index, ctx, args);
+            if !registry.register(Command::new(&command.name, &command.usage, &command.help, 0..=usize::MAX, run)) {
+                self.print(format!("{}: there already is a /{} command", self.scripts[command.hook.script].name, command.name));
+            }
+        }
+    }
+
+    fn run_command(&self, index: usize, ctx: &CommandContext, args: &Arguments) -> CommandResult {
+        let command = &self.commands[index];
+        let handle = WorldHandle::new(ctx.world.clone(), ctx.player);
+        let words: Array = (0..args.len()).filter_map(|i| args.get(i)).map(|word| Dynamic::from(word.to_string())).collect();
+        let result = self.with_budget(|| self.try_call(&command.hook, (handle, words)));
+        match result {
+            Ok(value) if value.is_unit() => Ok(String::new()),
+            Ok(value) => Ok(value.to_string()),
+            Err(e) => Err(CommandError::Failed(e)),
+        }
+    }
+
+    /// Advances the world a tick and runs the hooks of scripted tiles, behaviours and events
+    pub fn tick(&self, world: &Rc<RefCell<World>>, player: EntityId) {
+        // The world's own update does not count against the script budget
+        world.borrow_mut().update();
+        self.with_budget(|| {
+            let handle = WorldHandle::new(world.clone(), player);
+            self.run_tile_updates(world, &handle);
+            // Changes made during the tick, along with those made before it by commands or the player
+            self.dispatch_events(world, &handle);
+            if !self.behaviours.is_empty() {
+                let behaving: Vec<(EntityId, String)> = world.borrow().ecs().storage::<ScriptBehaviour>().iter()
+                    .map(|(id, behaviour)| (id, behaviour.0.clone()))
+                    .collect();
+                for (id, name) in behaving {
+                    if let Some(hook) = self.behaviours.get(&name) {
+                        self.call(hook, (handle.clone(), id));
+                    }
+                }
+            }
+            for hook in self.handlers(EventKind::Tick) {
+                self.call(hook, (handle.clone(),));
+            }
+            self.dispatch_events(world, &handle);
+        });
+    }
+
+    /// Runs the interact hook of the tile at `pos`, returning false if it has none
+    pub fn interact(&self, world: &Rc<RefCell<World>>, player: EntityId, pos: &TilePosition) -> bool {
+        let hook = scripted_kind_at(&world.borrow(), pos)
+            .and_then(|kind| self.tiles.get(&kind.id))
+            .and_then(|hooks| hooks.interact.clone());
+        let Some(hook) = hook else {
+            return false;
+        };
+        let handle = WorldHandle::new(world.clone(), player);
+        self.with_budget(|| self.call(&hook, (handle, pos.0 as i64, pos.1 as i64)));
+        true
+    }
+
+    fn handlers(&self, kind: EventKind) -> &[Hook] {
+        self.events.get(&kind).map_or(&[], |hooks| hooks.as_slice())
+    }
+
+    /// Runs the update hooks of the scripted tiles the world queued. Those left once the budget runs
+    /// out are queued again for the next tick.
+    fn run_tile_updates(&self, world: &Rc<RefCell<World>>, handle: &WorldHandle) {
+        let updates = world.borrow_mut().take_script_updates();
+        let mut updated = HashSet::new();
+        for pos in updates {
+            // A tile updates once per tick however often it was queued
+            if !updated.insert(pos.clone()) {
+                continue;
+            }
+            if self.out_of_budget.get() {
+                world.borrow_mut().queue_script_update(&pos);
+                continue;
+            }
+            // Earlier hooks may have replaced the tile
+            let hook = scripted_kind_at(&world.borrow(), &pos)
+                .and_then(|kind| self.tiles.get(&kind.id))
+                .and_then(|hooks| hooks.update.as_ref());
+            if let Some(hook) = hook {
+                self.call(hook, (handle.clone(), pos.0 as i64, pos.1 as i64));
+            }
+        }
+    }
+
+    fn dispatch_events(&self, world: &Rc<RefCell<World>>, handle: &WorldHandle) {
+        let events = world.borrow_mut().take_events();
+        for event in events {
+            match event {
+                WorldEvent::TileChanged(pos) => for hook in self.handlers(EventKind::TileChanged) {
+                    self.call(hook, (handle.clone(), pos.0 as i64, pos.1 as i64));
+                },
+                WorldEvent::EntitySpawned(id) => for hook in self.handlers(EventKind::EntitySpawned) {
+                    self.call(hook, (handle.clone(), id));
+                },
+            }
+        }
+    }
+
+    /// Runs `f` with a fresh time budget
+    fn with_budget<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.deadline.set(Some(Instant::now() + TICK_BUDGET));
+        self.out_of_budget.set(false);
+        let result = f();
+        self.deadline.set(None);
+        result
+    }
+
+    /// Calls a hook, logging any error
+    fn call(&self, hook: &Hook, args: impl FuncArgs) -> Option<Dynamic> {
+        match self.try_call(hook, args) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.print(e);
+                None
+            }
+        }
+    }
+
+    fn try_call(&self, hook: &Hook, args: impl FuncArgs) -> Result<Dynamic, String> {
+        let script = &self.scripts[hook.script];
+        if self.out_of_budget.get() {
+            return Err(format!("{}: skipped, scripts are over their time budget", script.name));
+        }
+        hook.function.call::<Dynamic>(&self.engine, &script.ast, args).map_err(|e| {
+            if matches!(*e, EvalAltResult::ErrorTerminated(..)) {
+                self.out_of_budget.set(true);
+                format!("{}: stopped after going over the {}ms time budget", script.name, TICK_BUDGET.as_millis())
+            } else {
+                format!("{}: {}", script.name, e)
+            }
+        })
+    }
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        ScriptHost::empty()
+    }
+}
+
+fn scripted_kind_at(world: &World, pos: &TilePosition) -> Option<&'static ScriptTileKind> {
+    world.get_tile(pos)
+        .and_then(|tile| tile.as_any().downcast_ref::<ScriptedTile>())
+        .map(|tile| tile.kind())
+}
+
+fn script_paths(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// An engine that can't reach the file system and stops scripts once the deadline passes
+fn sandboxed_engine(deadline: &Rc<Cell<Option<Instant>>>, output: &Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(4096);
+    engine.set_max_map_size(1024);
+    let deadline = deadline.clone();
+    engine.on_progress(move |operations| {
+        // Checking the time is slow compared to an operation
+        if operations % 256 != 0 {
+            return None;
+        }
+        deadline.get().filter(|deadline| Instant::now() > *deadline).map(|_| Dynamic::UNIT)
+    });
+    let print_output = output.clone();
+    engine.on_print(move |text| print_output.borrow_mut().push(text.to_string()));
+    let debug_output = output.clone();
+    engine.on_debug(move |text, _, _| debug_output.borrow_mut().push(text.to_string()));
+    api::register(&mut engine);
+    engine
+}
+
+/// Adds the functions scripts register things with. `current` is the index and name of the script
+/// that is running.
+fn register_loaders(engine: &mut Engine, registrations: &Rc<RefCell<Registrations>>, current: &Rc<RefCell<(usize, String)>>) {
+    let (reg, cur) = (registrations.clone(), current.clone());
+    engine.register_fn("register_tile", move |name: &str, options: Map| -> Result<(), Box<EvalAltResult>> {
+        let (script, script_name) = cur.borrow().clone();
+        let id = format!("{}:{}", script_name, name);
+        if reg.borrow().tiles.iter().any(|(kind, _)| kind.id == id) || plugin::new_tile(&id).is_some() {
+            return Err(format!("tile '{}' is already registered", id).into());
+        }
+        let color = match options.get("color") {
+            Some(color) => parse_color(color).ok_or("color must be an array of 3 numbers from 0 to 255")?,
+            None => Color::new(0xff, 0xff, 0, 0xff),
+        };
+        let solid = options.get("solid").map_or(Ok(true), |solid| solid.as_bool())
+            .map_err(|_| "solid must be true or false")?;
+        let hook = |key: &str| -> Result<Option<Hook>, Box<EvalAltResult>> {
+            match options.get(key) {
+                Some(value) => value.clone().try_cast::<FnPtr>()
+                    .map(|function| Some(Hook { script, function }))
+                    .ok_or_else(|| format!("{} must be a function pointer, such as Fn(\"name\")", key).into()),
+                None => Ok(None),
+            }
+        };
+        let hooks = TileHooks { update: hook("update")?, interact: hook("interact")? };
+        reg.borrow_mut().tiles.push((ScriptTileKind { id, color, solid }, hooks));
+        Ok(())
+    });
+    let (reg, cur) = (registrations.clone(), current.clone());
+    engine.register_fn("register_behaviour", move |name: &str, function: FnPtr| {
+        let script = cur.borrow().0;
+        reg.borrow_mut().behaviours.insert(name.to_string(), Hook { script, function });
+    });
+    let (reg, cur) = (registrations.clone(), current.clone());
+    engine.register_fn("register_command", move |name: &str, usage: &str, help: &str, function: FnPtr| {
+        let script = cur.borrow().0;
+        reg.borrow_mut().commands.push(ScriptCommand {
+            name: name.trim_start_matches('/').to_string(),
+            usage: usage.to_string(),
+            help: help.to_string(),
+            hook: Hook { script, function },
+        });
+    });
+    let (reg, cur) = (registrations.clone(), current.clone());
+    engine.register_fn("on", move |event: &str, function: FnPtr| -> Result<(), Box<EvalAltResult>> {
+        let kind = EventKind::from_str(event).map_err(|_| format!("unknown event '{}'", event))?;
+        let script = cur.borrow().0;
+        reg.borrow_mut().events.entry(kind).or_default().push(Hook { script, function });
+        Ok(())
+    });
+}
+
+fn parse_color(value: &Dynamic) -> Option<Color> {
+    let parts = value.clone().try_cast::<Array>()?;
+    let channels: Vec<u8> = parts.iter()
+        .map(|part| part.as_int().ok().filter(|c| (0..=255).contains(c)).map(|c| c as u8))
+        .collect::<Option<_>>()?;
+    match channels.as_slice() {
+        [r, g, b] => Some(Color::new(0xff, *r, *g, *b)),
+        _ => None,
+    }
+}
@@ -0,0 +1,136 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use rhai::{Array, Dynamic, Engine, EvalAltResult};
+use crate::coords;
+use crate::ecs::component::{Health, Position, ScriptBehaviour, Velocity};
+use crate::ecs::EntityId;
+use crate::entity;
+use crate::tile;
+use crate::world::World;
+use crate::{FloatVector2D, TilePosition};
+
+type ApiResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// The world as scripts see it. Every method borrows the world only for its own duration,
+/// so hooks must not be called while the world is borrowed.
+#[derive(Clone)]
+pub struct WorldHandle {
+    world: Rc<RefCell<World>>,
+    player: EntityId,
+}
+
+impl WorldHandle {
+    pub fn new(world: Rc<RefCell<World>>, player: EntityId) -> WorldHandle {
+        WorldHandle { world, player }
+    }
+}
+
+fn tile_pos(world: &World, x: i64, y: i64) -> ApiResult<TilePosition> {
+    if x < 0 || y < 0 || x as usize >= world.width() || y as usize >= world.height() {
+        return Err(format!("({},{}) is outside of the world", x, y).into());
+    }
+    Ok(TilePosition(x as usize, y as usize))
+}
+
+fn no_entity(id: EntityId) -> Box<EvalAltResult> {
+    format!("entity {} has no such component", id).into()
+}
+
+/// Makes the world API available to scripts
+pub fn register(engine: &mut Engine) {
+    engine.register_type_with_name::<WorldHandle>("World");
+    engine.register_type_with_name::<EntityId>("Entity");
+    engine.register_fn("to_string", |id: &mut EntityId| id.to_string());
+    engine.register_fn("to_debug", |id: &mut EntityId| id.to_string());
+    engine.register_fn("==", |a: EntityId, b: EntityId| a == b);
+    engine.register_fn("!=", |a: EntityId, b: EntityId| a != b);
+
+    engine.register_fn("width", |w: &mut WorldHandle| w.world.borrow().width() as i64);
+    engine.register_fn("height", |w: &mut WorldHandle| w.world.borrow().height() as i64);
+    engine.register_fn("tick", |w: &mut WorldHandle| w.world.borrow().tick() as i64);
+    engine.register_fn("time", |w: &mut WorldHandle| w.world.borrow().clock().time() as i64);
+    engine.register_fn("player", |w: &mut WorldHandle| w.player);
+
+    engine.register_fn("tile", |w: &mut WorldHandle, x: i64, y: i64| -> ApiResult<String> {
+        let world = w.world.borrow();
+        let pos = tile_pos(&world, x, y)?;
+        Ok(world.get_tile(&pos).and_then(|t| tile::name_of(t.as_ref())).unwrap_or_else(|| "air".to_string()))
+    });
+    engine.register_fn("set_tile", |w: &mut WorldHandle, x: i64, y: i64, name: &str| -> ApiResult<()> {
+        let mut world = w.world.borrow_mut();
+        let pos = tile_pos(&world, x, y)?;
+        let tile = tile::from_name(name).ok_or_else(|| format!("unknown tile '{}'", name))?;
+        world.set_tile(&pos, tile);
+        Ok(())
+    });
+    engine.register_fn("schedule_tick", |w: &mut WorldHandle, x: i64, y: i64, delay: i64| -> ApiResult<()> {
+        let mut world = w.world.borrow_mut();
+        let pos = tile_pos(&world, x, y)?;
+        world.schedule_tick(&pos, delay.max(1) as u64);
+        Ok(())
+    });
+    engine.register_fn("is_solid", |w: &mut WorldHandle, x: i64, y: i64| -> ApiResult<bool> {
+        let world = w.world.borrow();
+        let pos = tile_pos(&world, x, y)?;
+        Ok(world.is_occupied(&pos))
+    });
+    engine.register_fn("light", |w: &mut WorldHandle, x: i64, y: i64| -> ApiResult<i64> {
+        let world = w.world.borrow();
+        let pos = tile_pos(&world, x, y)?;
+        Ok(world.light_at(&pos) as i64)
+    });
+
+    engine.register_fn("spawn_entity", |w: &mut WorldHandle, kind: &str, x: i64, y: i64| -> ApiResult<EntityId> {
+        let mut world = w.world.borrow_mut();
+        let pos = tile_pos(&world, x, y)?;
+        entity::spawn_named(&mut world, kind, coords::tile_to_world(&pos)).ok_or_else(|| format!("unknown entity '{}'", kind).into())
+    });
+    engine.register_fn("despawn", |w: &mut WorldHandle, id: EntityId| {
+        w.world.borrow_mut().ecs_mut().despawn(id);
+    });
+    engine.register_fn("is_alive", |w: &mut WorldHandle, id: EntityId| w.world.borrow().ecs().is_alive(id));
+    engine.register_fn("set_behaviour", |w: &mut WorldHandle, id: EntityId, name: &str| {
+        let mut world = w.world.borrow_mut();
+        if world.ecs().is_alive(id) {
+            world.ecs_mut().insert(id, ScriptBehaviour(name.to_string()));
+        }
+    });
+    engine.register_fn("position", |w: &mut WorldHandle, id: EntityId| -> ApiResult<Array> {
+        let world = w.world.borrow();
+        let pos = world.ecs().get::<Position>(id).ok_or_else(|| no_entity(id))?;
+        Ok(vec![Dynamic::from_float(pos.0.0 as f64), Dynamic::from_float(pos.0.1 as f64)])
+    });
+    engine.register_fn("tile_position", |w: &mut WorldHandle, id: EntityId| -> ApiResult<Array> {
+        let world = w.world.borrow();
+        let pos = world.ecs().get::<Position>(id).ok_or_else(|| no_entity(id))?.0.to_tile_coords();
+        Ok(vec![Dynamic::from_int(pos.0 as i64), Dynamic::from_int(pos.1 as i64)])
+    });
+    engine.register_fn("velocity", |w: &mut WorldHandle, id: EntityId| -> ApiResult<Array> {
+        let world = w.world.borrow();
+        let vel = world.ecs().get::<Velocity>(id).ok_or_else(|| no_entity(id))?;
+        Ok(vec![Dynamic::from_float(vel.0.0 as f64), Dynamic::from_float(vel.0.1 as f64)])
+    });
+    engine.register_fn("set_velocity", |w: &mut WorldHandle, id: EntityId, x: f64, y: f64| -> ApiResult<()> {
+        let world = w.world.borrow();
+        let mut vel = world.ecs().get_mut::<Velocity>(id).ok_or_else(|| no_entity(id))?;
+        vel.0 = FloatVector2D(x as f32, y as f32);
+        Ok(())
+    });
+    engine.register_fn("health", |w: &mut WorldHandle, id: EntityId| -> ApiResult<i64> {
+        let world = w.world.borrow();
+        let health = world.ecs().get::<Health>(id).ok_or_else(|| no_entity(id))?;
+        Ok(health.current as i64)
+    });
+    engine.register_fn("damage", |w: &mut WorldHandle, id: EntityId, amount: i64| -> ApiResult<()> {
+        let world = w.world.borrow();
+        let mut health = world.ecs().get_mut::<Health>(id).ok_or_else(|| no_entity(id))?;
+        health.take_damage(amount.clamp(0, u8::MAX as i64) as u8);
+        Ok(())
+    });
+    engine.register_fn("heal", |w: &mut WorldHandle, id: EntityId, amount: i64| -> ApiResult<()> {
+        let world = w.world.borrow();
+        let mut health = world.ecs().get_mut::<Health>(id).ok_or_else(|| no_entity(id))?;
+        health.current = (health.current as i64 + amount.max(0)).min(health.max as i64) as u8;
+        Ok(())
+    });
+}
@@ -0,0 +1,386 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use rand::Rng;
+use serde::Deserialize;
+use crate::ai::{distance, Brain};
+use crate::ecs::component::{Controller, Position};
+use crate::ecs::EntityId;
+use crate::entity::EntityType;
+use crate::{EntityPosition, TilePosition};
+use crate::pathfinding::is_walkable;
+use crate::tile::registry;
+use crate::world::{Biome, World, MAX_LIGHT};
+
+/// Name of the data file in the assets directory
+pub const FILE: &str = "spawning.toml";
+/// Spawn rules shipped with the game, used when the data file can't be loaded
+const BUILTIN: &str = include_str!("../assets/spawning.toml");
+
+/// The loaded rules, set once at startup
+pub static RULES: OnceLock<Vec<SpawnRule>> = OnceLock::new();
+
+/// Where and how often a mob type may appear
+#[derive(Clone, Debug)]
+pub struct SpawnRule {
+    pub entity: EntityType,
+    /// Ids of the tiles the mob may stand on
+    pub surfaces: Vec<String>,
+    pub light: RangeInclusive<u8>,
+    pub biomes: Vec<Biome>,
+    /// Allowed distance in pixels from the nearest player
+    pub player_distance: RangeInclusive<f32>,
+    /// Most mobs of this type alive at once
+    pub cap: usize,
+    /// Chance per spawn cycle to attempt a spawn
+    pub chance: f64,
+    /// Mobs further than this from every player are removed
+    pub despawn_distance: f32,
+}
+
+/// A spawn rule as declared in the data file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDef {
+    entity: String,
+    surfaces: Vec<String>,
+    light: [u8; 2],
+    biomes: Vec<Biome>,
+    player_distance: [f32; 2],
+    cap: usize,
+    chance: f64,
+    despawn_distance: f32,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    rules: Vec<RuleDef>,
+}
+
+#[derive(Debug)]
+pub enum SpawnRuleError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    UnknownEntity(String),
+    UnknownSurface { entity: String, tile: String },
+    TooBright(String),
+    InvalidChance(String),
+}
+
+impl Display for SpawnRuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnRuleError::Io(e) => write!(f, "could not read spawn rules: {}", e),
+            SpawnRuleError::Parse(e) => write!(f, "invalid spawn rules: {}", e),
+            SpawnRuleError::UnknownEntity(name) => write!(f, "spawn rule for unknown entity '{}'", name),
+            SpawnRuleError::UnknownSurface { entity, tile } => write!(f, "'{}' spawns on unknown tile '{}'", entity, tile),
+            SpawnRuleError::TooBright(entity) => write!(f, "'{}' spawns in light above {}", entity, MAX_LIGHT),
+            SpawnRuleError::InvalidChance(entity) => write!(f, "'{}' has a spawn chance outside of 0 to 1", entity),
+        }
+    }
+}
+
+impl From<std::io::Error> for SpawnRuleError {
+    fn from(e: std::io::Error) -> Self {
+        SpawnRuleError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for SpawnRuleError {
+    fn from(e: toml::de::Error) -> Self {
+        SpawnRuleError::Parse(e)
+    }
+}
+
+/// Reads the rules in the data file at `path`
+pub fn load(path: &Path) -> Result<Vec<SpawnRule>, SpawnRuleError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// The rules shipped with the game
+pub fn builtin() -> Vec<SpawnRule> {
+    parse(BUILTIN).expect("built in spawn rules are valid")
+}
+
+fn parse(text: &str) -> Result<Vec<SpawnRule>, SpawnRuleError> {
+    let manifest: Manifest = toml::from_str(text)?;
+    manifest.rules.into_iter().map(|def| {
+        let entity = EntityType::from_str(&def.entity).map_err(|_| SpawnRuleError::UnknownEntity(def.entity.clone()))?;
+        // Only tiles from the registry have the definition spawning checks the surface with
+        if let Some(tile) = def.surfaces.iter().find(|id| registry::def(id).is_none()) {
+            return Err(SpawnRuleError::UnknownSurface { entity: def.entity, tile: tile.clone() });
+        }
+        if def.light[1] > MAX_LIGHT {
+            return Err(SpawnRuleError::TooBright(def.entity));
+        }
+        if !(0.0..=1.0).contains(&def.chance) {
+            return Err(SpawnRuleError::InvalidChance(def.entity));
+        }
+        Ok(SpawnRule {
+            entity,
+            surfaces: def.surfaces,
+            light: def.light[0]..=def.light[1],
+            biomes: def.biomes,
+            player_distance: def.player_distance[0]..=def.player_distance[1],
+            cap: def.cap,
+            chance: def.chance,
+            despawn_distance: def.despawn_distance,
+        })
+    }).collect()
+}
+
+/// The spawn rules used by default worlds, the built in ones if none were loaded
+pub fn default_rules() -> Vec<SpawnRule> {
+    RULES.get_or_init(builtin).clone()
+}
+
+/// Mobs a spawn cycle adds and removes
+#[derive(Default)]
+pub struct SpawnCycle {
+    pub spawned: Vec<(EntityType, EntityPosition)>,
+    pub despawned: Vec<EntityId>,
+}
+
+/// Ticks between spawn cycles
+const SPAWN_INTERVAL: u32 = 30;
+/// Random cells tried per rule each cycle
+const SPAWN_ATTEMPTS: usize = 8;
+
+pub struct Spawner {
+    rules: Vec<SpawnRule>,
+    ticks: u32,
+}
+
+impl Spawner {
+    pub fn new(rules: Vec<SpawnRule>) -> Spawner {
+        Spawner {
+            rules,
+            ticks: 0,
+        }
+    }
+
+    /// Runs a spawn and despawn cycle every `SPAWN_INTERVAL` ticks, for the world to apply
+    pub fn update(&mut self, world: &World) -> SpawnCycle {
+        let mut cycle = SpawnCycle::default();
+        self.ticks += 1;
+        if self.ticks < SPAWN_INTERVAL {
+            return cycle;
+        }
+        self.ticks = 0;
+        cycle.despawned = self.far_mobs(world);
+        for rule in self.rules.iter() {
+            let roll = world.rng().gen_bool(rule.chance);
+            let pending = cycle.spawned.iter().filter(|(kind, _)| *kind == rule.entity).count();
+            if roll && count(world, rule.entity) + pending < rule.cap {
+                if let Some(pos) = find_spawn(world, rule) {
+                    cycle.spawned.push((rule.entity, pos));
+                }
+            }
+        }
+        cycle
+    }
+
+    /// Mobs that have wandered too far from every player
+    fn far_mobs(&self, world: &World) -> Vec<EntityId> {
+        let players = player_positions(world);
+        // Nobody to be far from
+        if players.is_empty() {
+            return vec![];
+        }
+        let mut far = vec![];
+        {
+            let ecs = world.ecs();
+            let kinds = ecs.storage::<EntityType>();
+            let positions = ecs.storage::<Position>();
+            for (id, _) in ecs.storage::<Brain>().iter() {
+                let (Some(kind), Some(Position(pos))) = (kinds.get(id), positions.get(id)) else {
+                    continue;
+                };
+                let Some(rule) = self.rules.iter().find(|r| r.entity == *kind) else {
+                    continue;
+                };
+                if players.iter().all(|p| distance(p, pos) > rule.despawn_distance) {
+                    far.push(id);
+                }
+            }
+        }
+        far
+    }
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Spawner::new(default_rules())
+    }
+}
+
+fn count(world: &World, kind: EntityType) -> usize {
+    world.ecs().storage::<EntityType>().iter().filter(|(_, k)| **k == kind).count()
+}
+
+fn player_positions(world: &World) -> Vec<EntityPosition> {
+    let ecs = world.ecs();
+    let positions = ecs.storage::<Position>();
+    ecs.storage::<Controller>().iter()
+        .filter_map(|(id, _)| positions.get(id).map(|p| p.0.clone()))
+        .collect()
+}
+
+/// Picks random cells until one satisfies the rule
+pub fn find_spawn(world: &World, rule: &SpawnRule) -> Option<EntityPosition> {
+    let players = player_positions(world);
+    // Mobs stand on the tile below them, so the bottom row is never picked
+    if players.is_empty() || world.height() < 2 {
+        return None;
+    }
+    for _ in 0..SPAWN_ATTEMPTS {
+        let pos = {
+            let mut rng = world.rng();
+            TilePosition(rng.gen_range(0..world.width()), rng.gen_range(1..world.height()))
+        };
+        if can_spawn(world, rule, &pos, &players) {
+            return Some(pos.to_entity_pos());
+        }
+    }
+    None
+}
+
+/// Whether a mob following `rule` may appear standing in `pos`, never without players around
+pub fn can_spawn(world: &World, rule: &SpawnRule, pos: &TilePosition, players: &[EntityPosition]) -> bool {
+    if players.is_empty() || !is_walkable(world, pos) {
+        return false;
+    }
+    let below = TilePosition(pos.0, pos.1 - 1);
+    if !world.get_def(&below).is_some_and(|def| rule.surfaces.contains(&def.id)) {
+        return false;
+    }
+    if !rule.light.contains(&world.light_at(pos)) || !rule.biomes.contains(&world.biome_at(pos)) {
+        return false;
+    }
+    let entity_pos = pos.to_entity_pos();
+    players.iter().all(|p| rule.player_distance.contains(&distance(p, &entity_pos)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::coords;
+    use crate::entity;
+    use crate::tile::base::BaseTile;
+    use super::*;
+
+    /// A world with bedrock below a row of grass, stone on the right half
+    fn flat_world(width: usize, height: usize, seed: u64) -> Rc<RefCell<World>> {
+        World::build(width, height, seed, |world| {
+            for x in 0..width {
+                world.set_tile(&TilePosition(x, 0), BaseTile::named("bedrock").unwrap());
+                if height > 1 {
+                    let surface = if x < width / 2 { "grass" } else { "stone" };
+                    world.set_tile(&TilePosition(x, 1), BaseTile::named(surface).unwrap());
+                }
+            }
+        })
+    }
+
+    fn rule(surfaces: &[&str]) -> SpawnRule {
+        SpawnRule {
+            entity: EntityType::Slime,
+            surfaces: surfaces.iter().map(|s| s.to_string()).collect(),
+            light: 0..=MAX_LIGHT,
+            biomes: vec![Biome::Surface, Biome::Underground],
+            player_distance: 60.0..=300.0,
+            cap: 3,
+            chance: 1.0,
+            despawn_distance: 400.0,
+        }
+    }
+
+    fn center(pos: &TilePosition) -> EntityPosition {
+        coords::tile_center(pos)
+    }
+
+    #[test]
+    fn can_spawn_checks_surface_and_distance() {
+        let world = flat_world(40, 10, 1);
+        let world = world.borrow();
+        let grass = rule(&["grass"]);
+        let players = [center(&TilePosition(2, 2))];
+        assert!(can_spawn(&world, &grass, &TilePosition(10, 2), &players));
+        // Too close to the player, and too far away
+        assert!(!can_spawn(&world, &grass, &TilePosition(3, 2), &players));
+        assert!(!can_spawn(&world, &grass, &TilePosition(19, 2), &[center(&TilePosition(39, 2))]));
+        // Standing on stone, inside the ground and in mid air
+        assert!(!can_spawn(&world, &grass, &TilePosition(25, 2), &players));
+        assert!(!can_spawn(&world, &grass, &TilePosition(10, 1), &players));
+        assert!(!can_spawn(&world, &grass, &TilePosition(10, 5), &players));
+    }
+
+    #[test]
+    fn can_spawn_needs_players() {
+        let world = flat_world(40, 10, 1);
+        assert!(!can_spawn(&world.borrow(), &rule(&["grass"]), &TilePosition(10, 2), &[]));
+    }
+
+    #[test]
+    fn find_spawn_picks_valid_tiles() {
+        let world = flat_world(40, 3, 2);
+        entity::spawn(&mut world.borrow_mut(), EntityType::Player, center(&TilePosition(20, 2)));
+        let rule = rule(&["grass", "stone"]);
+        let players = player_positions(&world.borrow());
+        let mut found = 0;
+        for _ in 0..50 {
+            if let Some(pos) = find_spawn(&world.borrow(), &rule) {
+                assert!(can_spawn(&world.borrow(), &rule, &coords::world_to_tile(&pos), &players));
+                found += 1;
+            }
+        }
+        assert!(found > 0);
+    }
+
+    #[test]
+    fn find_spawn_repeats_with_the_seed() {
+        let spawns = |seed| {
+            let world = flat_world(40, 3, seed);
+            entity::spawn(&mut world.borrow_mut(), EntityType::Player, center(&TilePosition(20, 2)));
+            let rule = rule(&["grass", "stone"]);
+            (0..20).map(|_| find_spawn(&world.borrow(), &rule).map(|p| coords::world_to_tile(&p))).collect::<Vec<_>>()
+        };
+        assert_eq!(spawns(7), spawns(7));
+    }
+
+    #[test]
+    fn find_spawn_without_room_or_players() {
+        let world = flat_world(40, 1, 3);
+        entity::spawn(&mut world.borrow_mut(), EntityType::Player, center(&TilePosition(20, 0)));
+        assert!(find_spawn(&world.borrow(), &rule(&["bedrock"])).is_none());
+        let world = flat_world(40, 3, 3);
+        assert!(find_spawn(&world.borrow(), &rule(&["grass", "stone"])).is_none());
+    }
+
+    #[test]
+    fn mobs_stay_without_players() {
+        let world = flat_world(40, 3, 4);
+        entity::spawn(&mut world.borrow_mut(), EntityType::Slime, center(&TilePosition(5, 2)));
+        assert!(Spawner::new(vec![rule(&["grass"])]).far_mobs(&world.borrow()).is_empty());
+    }
+
+    #[test]
+    fn cycles_stay_under_the_cap() {
+        let world = flat_world(40, 10, 5);
+        entity::spawn(&mut world.borrow_mut(), EntityType::Player, center(&TilePosition(2, 2)));
+        let mut spawner = Spawner::new(vec![rule(&["grass"]), rule(&["grass"])]);
+        for _ in 1..SPAWN_INTERVAL {
+            assert!(spawner.update(&world.borrow()).spawned.is_empty());
+        }
+        let cycle = spawner.update(&world.borrow());
+        assert!(!cycle.spawned.is_empty() && cycle.spawned.len() <= 3);
+    }
+
+    #[test]
+    fn builtin_rules_load() {
+        assert!(!builtin().is_empty());
+    }
+}
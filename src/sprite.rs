@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::path::Path;
+use std::sync::OnceLock;
+use raqote::{DrawOptions, DrawTarget, Image};
+use serde::Deserialize;
+use crate::entity::EntityType;
+use crate::tile::autotile::Connections;
+
+/// The atlas loaded at startup, None when no atlas could be loaded and solid colors are drawn instead
+pub static ATLAS: OnceLock<Option<Atlas>> = OnceLock::new();
+
+pub fn atlas() -> Option<&'static Atlas> {
+    ATLAS.get().and_then(|atlas| atlas.as_ref())
+}
+
+/// The file describing where each sprite is in the atlas image
+pub const MANIFEST_FILE: &str = "atlas.toml";
+
+/// An x, y, width, height rect in atlas pixels
+type AtlasRect = [u32; 4];
+
+#[derive(Deserialize)]
+struct FrameDef {
+    rect: AtlasRect,
+    /// How many ticks the frame is shown for
+    ticks: u32,
+}
+
+/// A manifest entry, either a single still rect or a sequence of frames
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpriteDef {
+    Still(AtlasRect),
+    Frames(Vec<FrameDef>),
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    /// Path of the atlas image, relative to the manifest
+    image: String,
+    /// Tile texture name to sprite, such as `stone`. Connected variants add the
+    /// [`Connections`] mask after a dot, such as `grass.10` for grass with neighbours east and west.
+    #[serde(default)]
+    tiles: HashMap<String, SpriteDef>,
+    /// Entity type and animation state to sprite, such as `player.walk`
+    #[serde(default)]
+    entities: HashMap<String, SpriteDef>,
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(std::io::Error),
+    Manifest(toml::de::Error),
+    Png(png::DecodingError),
+    InvalidVariant(String),
+    OutOfBounds(String),
+    EmptyAnimation(String),
+}
+
+impl Display for AtlasError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtlasError::Io(e) => write!(f, "could not read atlas: {}", e),
+            AtlasError::Manifest(e) => write!(f, "invalid atlas manifest: {}", e),
+            AtlasError::Png(e) => write!(f, "invalid atlas image: {}", e),
+            AtlasError::InvalidVariant(name) => write!(f, "tile variant '{}' must be a mask below {}", name, Connections::COUNT),
+            AtlasError::OutOfBounds(name) => write!(f, "sprite '{}' is outside of the atlas image", name),
+            AtlasError::EmptyAnimation(name) => write!(f, "sprite '{}' has no frames or a frame of 0 ticks", name),
+        }
+    }
+}
+
+impl From<std::io::Error> for AtlasError {
+    fn from(e: std::io::Error) -> Self {
+        AtlasError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for AtlasError {
+    fn from(e: toml::de::Error) -> Self {
+        AtlasError::Manifest(e)
+    }
+}
+
+impl From<png::DecodingError> for AtlasError {
+    fn from(e: png::DecodingError) -> Self {
+        AtlasError::Png(e)
+    }
+}
+
+/// A single image cut out of the atlas, stored as premultiplied ARGB like raqote expects
+pub struct Sprite {
+    width: i32,
+    height: i32,
+    data: Vec<u32>,
+    /// The same image mirrored horizontally
+    flipped: Vec<u32>,
+}
+
+impl Sprite {
+    /// Draws the sprite scaled to the given screen rect, mirrored if `flip` is set
+    pub fn draw(&self, target: &mut DrawTarget, x: f32, y: f32, width: f32, height: f32, flip: bool) {
+        let image = Image {
+            width: self.width,
+            height: self.height,
+            data: if flip { &self.flipped } else { &self.data },
+        };
+        if width == self.width as f32 && height == self.height as f32 {
+            target.draw_image_at(x, y, &image, &DrawOptions::new());
+        } else {
+            target.draw_image_with_size_at(width, height, x, y, &image, &DrawOptions::new());
+        }
+    }
+}
+
+/// Frames shown one after another, looping. A still sprite is an animation with one frame.
+pub struct Animation {
+    frames: Vec<(Sprite, u32)>,
+    /// Sum of all frame durations
+    length: u32,
+}
+
+impl Animation {
+    /// The frame showing `ticks` ticks after the animation started
+    pub fn frame_at(&self, ticks: u64) -> &Sprite {
+        let mut time = (ticks % self.length as u64) as u32;
+        for (sprite, duration) in self.frames.iter() {
+            if time < *duration {
+                return sprite;
+            }
+            time -= duration;
+        }
+        &self.frames[0].0
+    }
+}
+
+pub struct Atlas {
+    /// Tile sprites by name, see [`crate::tile::registry::TileDef::sprite`]
+    tiles: HashMap<String, Animation>,
+    variants: HashMap<(String, Connections), Animation>,
+    entities: HashMap<String, Animation>,
+}
+
+impl Atlas {
+    /// Loads the manifest in `dir` and the image it points to
+    pub fn load(dir: &Path) -> Result<Atlas, AtlasError> {
+        let manifest: Manifest = toml::from_str(&std::fs::read_to_string(dir.join(MANIFEST_FILE))?)?;
+        let (width, height, pixels) = read_png(&dir.join(&manifest.image))?;
+        let cut = |name: &str, rect: &AtlasRect| -> Result<Sprite, AtlasError> {
+            let [x, y, w, h] = *rect;
+            if x + w > width || y + h > height {
+                return Err(AtlasError::OutOfBounds(name.to_string()));
+            }
+            let pixel = |row: u32, col: u32| pixels[(row * width + col) as usize];
+            let data = (y..y + h).flat_map(|row| (x..x + w).map(move |col| pixel(row, col))).collect();
+            let flipped = (y..y + h).flat_map(|row| (x..x + w).rev().map(move |col| pixel(row, col))).collect();
+            Ok(Sprite { width: w as i32, height: h as i32, data, flipped })
+        };
+        let animate = |name: &str, def: &SpriteDef| -> Result<Animation, AtlasError> {
+            let frames = match def {
+                SpriteDef::Still(rect) => vec![(cut(name, rect)?, 1)],
+                SpriteDef::Frames(frames) => frames.iter()
+                    .map(|frame| Ok((cut(name, &frame.rect)?, frame.ticks)))
+                    .collect::<Result<Vec<_>, AtlasError>>()?,
+            };
+            if frames.is_empty() || frames.iter().any(|(_, ticks)| *ticks == 0) {
+                return Err(AtlasError::EmptyAnimation(name.to_string()));
+            }
+            let length = frames.iter().map(|(_, ticks)| ticks).sum();
+            Ok(Animation { frames, length })
+        };
+
+        let mut tiles = HashMap::new();
+        let mut variants = HashMap::new();
+        for (name, def) in manifest.tiles.iter() {
+            let (sprite, mask) = match name.split_once('.') {
+                Some((sprite, mask)) => (sprite.to_string(), Some(mask)),
+                None => (name.clone(), None),
+            };
+            match mask {
+                Some(mask) => {
+                    let mask = mask.parse::<u8>().ok()
+                        .filter(|mask| *mask < Connections::COUNT)
+                        .ok_or_else(|| AtlasError::InvalidVariant(name.clone()))?;
+                    variants.insert((sprite, Connections(mask)), animate(name, def)?);
+                }
+                None => {
+                    tiles.insert(sprite, animate(name, def)?);
+                }
+            }
+        }
+        let mut entities = HashMap::new();
+        for (name, def) in manifest.entities.iter() {
+            entities.insert(name.clone(), animate(name, def)?);
+        }
+        Ok(Atlas { tiles, variants, entities })
+    }
+
+    pub fn tile(&self, sprite: &str) -> Option<&Animation> {
+        self.tiles.get(sprite)
+    }
+
+    /// The variant of a tile for how it connects to its neighbours, or the plain tile if the atlas has none
+    pub fn tile_variant(&self, sprite: &str, connections: Connections) -> Option<&Animation> {
+        self.variants.get(&(sprite.to_string(), connections)).or_else(|| self.tile(sprite))
+    }
+
+    /// The animation for an entity type in a state, such as `walk`
+    pub fn entity(&self, kind: EntityType, state: &str) -> Option<&Animation> {
+        self.entities.get(&format!("{}.{}", kind, state))
+    }
+}
+
+/// Decodes a PNG into its size and premultiplied ARGB pixels
+fn read_png(path: &Path) -> Result<(u32, u32, Vec<u32>), AtlasError> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let channels = info.color_type.samples();
+    let pixels = buf[..info.buffer_size()].chunks_exact(channels)
+        .map(|px| {
+            let (r, g, b, a) = match channels {
+                1 => (px[0], px[0], px[0], 0xff),
+                2 => (px[0], px[0], px[0], px[1]),
+                3 => (px[0], px[1], px[2], 0xff),
+                _ => (px[0], px[1], px[2], px[3]),
+            };
+            let premultiply = |c: u8| c as u32 * a as u32 / 0xff;
+            (a as u32) << 24 | premultiply(r) << 16 | premultiply(g) << 8 | premultiply(b)
+        })
+        .collect();
+    Ok((info.width, info.height, pixels))
+}
@@ -1,24 +1,74 @@
 pub mod base;
 pub mod player;
 pub mod air;
+pub mod autotile;
+pub mod scripted;
+pub mod registry;
+pub mod meta;
+pub mod entity;
 
 use std::any::Any;
 use font_kit::font::Font;
 use raqote::DrawTarget;
+use crate::coords::ViewTransform;
+use crate::plugin;
 use crate::TilePosition;
+use crate::tile::air::EmptyTile;
+use crate::tile::base::BaseTile;
+use crate::tile::scripted::ScriptedTile;
+use crate::world::World;
 
 pub trait Tile {
-    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font);
-    fn update(&mut self);
+    /// Draws the tile, `tick` is the world tick that animations advance with
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font, view: &ViewTransform, tick: u64);
+    /// Runs when a tick scheduled for the tile is due, see [`World::schedule_tick`]. Placed tiles
+    /// get one on the next tick. While it runs the tile is out of the world, which sees air at `pos`.
+    fn update(&mut self, _world: &mut World, _pos: &TilePosition) {}
+    /// Runs after the tile sharing a side with it at `neighbor` changed, with the same caveat as `update`
+    fn on_neighbor_changed(&mut self, _world: &mut World, _pos: &TilePosition, _neighbor: &TilePosition) {}
     fn get_type(&self) -> &TileType;
+    /// Whether entities collide with the tile and it blocks light
+    fn is_solid(&self) -> bool {
+        self.get_type() != &TileType::Empty
+    }
 
     fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
 
 #[derive(PartialEq, Debug)]
 pub enum TileType {
     Empty,
-    Base, // TODO: Base(TileTexture)?
-    Player
+    /// Defined in the tile registry, see [`registry`]
+    Base,
+    Player,
+    /// A kind registered by a script, see [`scripted`]
+    Scripted,
+    /// A type registered by a plugin, see [`crate::plugin`]
+    Plugin,
+}
+
+/// The name a tile is known by in commands, scripts and saves: "air", or the id of a registered
+/// tile, scripted kind or plugin tile.
+/// None for tiles that can't be placed by name.
+pub fn name_of(tile: &dyn Tile) -> Option<String> {
+    match tile.get_type() {
+        TileType::Empty => Some("air".to_string()),
+        TileType::Base => tile.as_any().downcast_ref::<BaseTile>().map(|t| t.def().id.clone()),
+        TileType::Scripted => tile.as_any().downcast_ref::<ScriptedTile>().map(|t| t.kind().id.clone()),
+        TileType::Plugin => plugin::tile_id(tile).map(str::to_string),
+        TileType::Player => None,
+    }
+}
+
+/// Creates a tile from its name, see [`name_of`]
+pub fn from_name(name: &str) -> Option<Box<dyn Tile>> {
+    if name == "air" {
+        return Some(EmptyTile::new());
+    }
+    if let Some(tile) = BaseTile::named(name) {
+        return Some(tile);
+    }
+    ScriptedTile::new(name).map(|tile| tile as Box<dyn Tile>).or_else(|| plugin::new_tile(name))
 }
\ No newline at end of file
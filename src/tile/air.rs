@@ -2,6 +2,7 @@ use std::any::Any;
 use font_kit::font::Font;
 use minifb::Window;
 use raqote::DrawTarget;
+use crate::coords::ViewTransform;
 use crate::TilePosition;
 use crate::tile::{Tile, TileType};
 
@@ -14,11 +15,7 @@ impl EmptyTile {
     }
 }
 impl Tile for EmptyTile {
-    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font) {
-        // Do nothing
-    }
-
-    fn update(&mut self) {
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font, view: &ViewTransform, tick: u64) {
         // Do nothing
     }
 
@@ -29,4 +26,8 @@ impl Tile for EmptyTile {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+use crate::TilePosition;
+use crate::tile::base::BaseTile;
+use crate::world::World;
+
+/// Which of the four sides of a tile touch a tile of the same texture, as a bitmask.
+/// The mask picks the sprite variant, so a grass tile with nothing above it gets a grassy top.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Connections(pub u8);
+
+impl Connections {
+    pub const NORTH: u8 = 1;
+    pub const EAST: u8 = 2;
+    pub const SOUTH: u8 = 4;
+    pub const WEST: u8 = 8;
+    /// Number of distinct masks
+    pub const COUNT: u8 = 16;
+}
+
+/// Side bit and tile offset of each neighbour. North is up, which is +y in tile space.
+const NEIGHBOURS: [(u8, (isize, isize)); 4] = [
+    (Connections::NORTH, (0, 1)),
+    (Connections::EAST, (1, 0)),
+    (Connections::SOUTH, (0, -1)),
+    (Connections::WEST, (-1, 0)),
+];
+
+/// The neighbour of a tile, None when it would be left of or below the world
+pub fn neighbour(pos: &TilePosition, offset: (isize, isize)) -> Option<TilePosition> {
+    let x = pos.0.checked_add_signed(offset.0)?;
+    let y = pos.1.checked_add_signed(offset.1)?;
+    Some(TilePosition(x, y))
+}
+
+/// The four tiles sharing a side with the position, skipping any that would be out of the world
+pub fn neighbours(pos: &TilePosition) -> impl Iterator<Item = TilePosition> + '_ {
+    NEIGHBOURS.iter().filter_map(|(_, offset)| neighbour(pos, *offset))
+}
+
+/// Works out the connections of the tile at position from its current neighbours
+pub fn connections_at(world: &World, pos: &TilePosition) -> Connections {
+    let Some(def) = world.get_def(pos) else {
+        return Connections::default();
+    };
+    let mut mask = 0;
+    for (side, offset) in NEIGHBOURS.iter() {
+        if neighbour(pos, *offset).is_some_and(|n| world.get_def(&n) == Some(def)) {
+            mask |= side;
+        }
+    }
+    Connections(mask)
+}
+
+/// Recomputes the connections of a tile and its neighbours after the tile changed
+pub fn refresh_around(world: &mut World, pos: &TilePosition) {
+    let positions: Vec<TilePosition> = std::iter::once(pos.clone()).chain(neighbours(pos)).collect();
+    for pos in positions {
+        let connections = connections_at(world, &pos);
+        let base = world.get_tile_mut(&pos).and_then(|t| t.as_any_mut().downcast_mut::<BaseTile>());
+        if let Some(base) = base {
+            base.set_connections(connections);
+        }
+    }
+}
@@ -1,61 +1,71 @@
 use std::any::Any;
 use font_kit::font::Font;
 use minifb::Window;
-use raqote::{Color, DrawOptions, DrawTarget, SolidSource, Source};
-use crate::{GRID_SIZE, TilePosition, RENDER_BOUND, TILE_SIZE};
+use raqote::{DrawOptions, DrawTarget, SolidSource, Source};
+use crate::coords::ViewTransform;
+use crate::TilePosition;
+use crate::sprite;
 use crate::tile::{Tile, TileType};
+use crate::tile::autotile::Connections;
+use crate::tile::registry::{self, TileDef};
 
 
-#[derive(Clone, Debug, PartialOrd, PartialEq)]
-pub enum TileTexture {
-    Stone,
-    Bedrock,
-    Dirt,
-    Grass
-}
-
-impl TileTexture {
-    pub fn get_color(&self) -> Color {
-        match self {
-            TileTexture::Stone => Color::new(255, 89, 89, 87),
-            TileTexture::Bedrock => Color::new(255, 46, 46, 45),
-            TileTexture::Dirt => Color::new(255, 138, 90, 32),
-            TileTexture::Grass => Color::new(255, 12, 207, 67),
-            _ => Color::new(255, 255, 255, 255)
-        }
-    }
-}
-
+/// A tile made of a definition from the tile registry
 pub struct BaseTile {
-    texture: TileTexture
+    def: &'static TileDef,
+    /// Kept up to date by the world whenever a neighbour changes, see [`crate::tile::autotile`]
+    connections: Connections,
 }
 
 impl Tile for BaseTile {
-    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font) {
-        target.fill_rect(pos.0 as f32 * TILE_SIZE, RENDER_BOUND as f32 - (pos.1 as f32 * TILE_SIZE), TILE_SIZE, TILE_SIZE, &Source::Solid(SolidSource::from(self.texture.get_color())), &DrawOptions::new());
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font, view: &ViewTransform, tick: u64) {
+        let (x, y, w, h) = view.tile_to_screen(pos);
+        match sprite::atlas().and_then(|atlas| atlas.tile_variant(self.def.sprite(), self.connections)) {
+            Some(animation) => animation.frame_at(tick).draw(target, x, y, w, h, false),
+            None => target.fill_rect(x, y, w, h, &Source::Solid(SolidSource::from(self.def.color())), &DrawOptions::new()),
+        }
     }
 
-    fn update(&mut self) {
-
-    }
     fn get_type(&self) -> &TileType {
         &TileType::Base
     }
 
+    fn is_solid(&self) -> bool {
+        self.def.solid
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 impl BaseTile {
-    pub fn new(texture: TileTexture) -> Box<impl Tile> {
+    pub fn new(def: &'static TileDef) -> Box<BaseTile> {
         Box::new(BaseTile {
-            texture
+            def,
+            connections: Connections::default(),
         })
     }
 
-    pub fn get_texture(&self) -> &TileTexture {
-        &self.texture
+    /// A tile of the definition with `id`, None if there is none
+    pub fn named(id: &str) -> Option<Box<BaseTile>> {
+        registry::def(id).map(BaseTile::new)
+    }
+
+    pub fn def(&self) -> &'static TileDef {
+        self.def
+    }
+
+    pub fn connections(&self) -> Connections {
+        self.connections
+    }
+
+    pub fn set_connections(&mut self, connections: Connections) {
+        self.connections = connections;
     }
 }
 
@@ -0,0 +1,196 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use strum_macros::Display;
+use crate::plugin;
+
+/// Most stacks a chest holds
+pub const CHEST_SLOTS: usize = 27;
+/// Most items in one stack
+pub const MAX_STACK: u32 = 64;
+/// Longest text a sign shows
+pub const MAX_SIGN_LENGTH: usize = 64;
+/// Ticks a furnace takes to smelt one recipe
+const SMELT_TICKS: u32 = 200;
+
+/// Which tile entity a tile definition comes with, see [`crate::tile::registry::TileDef`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Display, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TileEntityKind {
+    Chest,
+    Sign,
+    Furnace,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// A tile or recipe output id
+    pub id: String,
+    pub count: u32,
+}
+
+impl Display for ItemStack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.count, self.id)
+    }
+}
+
+/// State a tile keeps beyond its metadata, stored by the world next to the tile
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TileEntity {
+    Chest(Chest),
+    Sign(Sign),
+    Furnace(Furnace),
+}
+
+impl TileEntity {
+    pub fn new(kind: TileEntityKind) -> TileEntity {
+        match kind {
+            TileEntityKind::Chest => TileEntity::Chest(Chest::default()),
+            TileEntityKind::Sign => TileEntity::Sign(Sign::default()),
+            TileEntityKind::Furnace => TileEntity::Furnace(Furnace::default()),
+        }
+    }
+
+    pub fn kind(&self) -> TileEntityKind {
+        match self {
+            TileEntity::Chest(_) => TileEntityKind::Chest,
+            TileEntity::Sign(_) => TileEntityKind::Sign,
+            TileEntity::Furnace(_) => TileEntityKind::Furnace,
+        }
+    }
+
+    /// Called every world tick
+    pub fn update(&mut self) {
+        if let TileEntity::Furnace(furnace) = self {
+            furnace.update();
+        }
+    }
+
+    /// Puts items in, returning what didn't fit. Only chests and furnaces take items.
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        match self {
+            TileEntity::Chest(chest) => chest.insert(stack),
+            TileEntity::Furnace(furnace) => furnace.insert(stack),
+            TileEntity::Sign(_) => Some(stack),
+        }
+    }
+
+    /// The items it holds
+    pub fn stacks(&self) -> Vec<&ItemStack> {
+        match self {
+            TileEntity::Chest(chest) => chest.items.iter().collect(),
+            TileEntity::Furnace(furnace) => furnace.input.iter().chain(furnace.output.iter()).collect(),
+            TileEntity::Sign(_) => vec![],
+        }
+    }
+}
+
+impl Display for TileEntity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileEntity::Chest(chest) if chest.items.is_empty() => write!(f, "empty chest"),
+            TileEntity::Chest(chest) => {
+                let items: Vec<String> = chest.items.iter().map(|stack| stack.to_string()).collect();
+                write!(f, "chest with {}", items.join(", "))
+            }
+            TileEntity::Sign(sign) => write!(f, "sign saying '{}'", sign.text),
+            TileEntity::Furnace(furnace) => {
+                let slot = |stack: &Option<ItemStack>| stack.as_ref().map_or("nothing".to_string(), |s| s.to_string());
+                write!(f, "furnace with {} to smelt and {} smelted ({}/{})", slot(&furnace.input), slot(&furnace.output), furnace.progress, SMELT_TICKS)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Chest {
+    #[serde(default)]
+    pub items: Vec<ItemStack>,
+}
+
+impl Chest {
+    /// Tops up stacks of the same item first, then fills empty slots
+    pub fn insert(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+        for existing in self.items.iter_mut().filter(|existing| existing.id == stack.id) {
+            let moved = stack.count.min(MAX_STACK.saturating_sub(existing.count));
+            existing.count += moved;
+            stack.count -= moved;
+        }
+        while stack.count > 0 && self.items.len() < CHEST_SLOTS {
+            let moved = stack.count.min(MAX_STACK);
+            self.items.push(ItemStack { id: stack.id.clone(), count: moved });
+            stack.count -= moved;
+        }
+        (stack.count > 0).then_some(stack)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sign {
+    #[serde(default)]
+    pub text: String,
+}
+
+impl Sign {
+    /// Sets the text, cut off at `MAX_SIGN_LENGTH` characters
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().take(MAX_SIGN_LENGTH).collect();
+    }
+}
+
+/// Smelts its input with the single input recipes plugins added
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Furnace {
+    pub input: Option<ItemStack>,
+    pub output: Option<ItemStack>,
+    /// Ticks spent on the current recipe
+    #[serde(default)]
+    pub progress: u32,
+}
+
+impl Furnace {
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        match &mut self.input {
+            None => {
+                let moved = stack.count.min(MAX_STACK);
+                self.input = Some(ItemStack { id: stack.id.clone(), count: moved });
+                (stack.count > moved).then(|| ItemStack { id: stack.id, count: stack.count - moved })
+            }
+            Some(input) if input.id == stack.id => {
+                let moved = stack.count.min(MAX_STACK.saturating_sub(input.count));
+                input.count += moved;
+                (stack.count > moved).then(|| ItemStack { id: stack.id, count: stack.count - moved })
+            }
+            Some(_) => Some(stack),
+        }
+    }
+
+    fn update(&mut self) {
+        let Some(input) = &self.input else {
+            self.progress = 0;
+            return;
+        };
+        let recipe = plugin::recipes().find(|recipe| {
+            matches!(recipe.inputs.as_slice(), [(id, count)] if *id == input.id && *count <= input.count)
+        });
+        let fits = |recipe: &plugin::Recipe| self.output.as_ref()
+            .is_none_or(|output| output.id == recipe.output.0 && output.count + recipe.output.1 <= MAX_STACK);
+        let Some(recipe) = recipe.filter(|recipe| fits(recipe)) else {
+            self.progress = 0;
+            return;
+        };
+        self.progress += 1;
+        if self.progress < SMELT_TICKS {
+            return;
+        }
+        self.progress = 0;
+        let used = recipe.inputs[0].1;
+        self.input = Some(ItemStack { id: input.id.clone(), count: input.count - used }).filter(|stack| stack.count > 0);
+        match &mut self.output {
+            Some(output) => output.count += recipe.output.1,
+            None => self.output = Some(ItemStack { id: recipe.output.0.clone(), count: recipe.output.1 }),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+/// Most growth stages a tile can go through
+pub const MAX_GROWTH: u8 = 15;
+
+/// Small per-position state kept for every tile, reset whenever the tile is replaced
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileMeta {
+    #[serde(default)]
+    pub orientation: Orientation,
+    /// From 0 to `MAX_GROWTH`, for tiles that grow
+    #[serde(default)]
+    pub growth: u8,
+    /// How worn the tile is, 0 being intact
+    #[serde(default)]
+    pub damage: u8,
+}
+
+impl TileMeta {
+    pub fn is_default(&self) -> bool {
+        *self == TileMeta::default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Display, EnumString, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
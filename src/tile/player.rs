@@ -2,8 +2,10 @@ use std::any::Any;
 use font_kit::font::Font;
 use minifb::Window;
 use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
-use crate::{TilePosition, RENDER_BOUND, TILE_SIZE};
+use crate::coords::ViewTransform;
+use crate::TilePosition;
 use crate::tile::{Tile, TileType};
+use crate::ui;
 
 pub struct PlayerTile {
 }
@@ -16,17 +18,13 @@ impl PlayerTile {
 }
 
 impl Tile for PlayerTile {
-    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font) {
-        let (x, y) = (pos.0 as f32 * TILE_SIZE, RENDER_BOUND - (pos.1 as f32 * TILE_SIZE));
-        target.draw_text(&font, 14., "Player", Point::new(x - (TILE_SIZE/2.0), y - 5.0),
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, font: &Font, view: &ViewTransform, tick: u64) {
+        let (x, y, w, h) = view.tile_to_screen(pos);
+        ui::draw_text(target, &font, 14., "Player", Point::new(x - (w/2.0), y - 5.0),
                      &Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0, 0)),
                      &DrawOptions::new(),
         );
-        target.fill_rect(x, y, TILE_SIZE, TILE_SIZE, &Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0, 0)), &DrawOptions::new());
-
-    }
-
-    fn update(&mut self) {
+        target.fill_rect(x, y, w, h, &Source::Solid(SolidSource::from_unpremultiplied_argb(0xff, 0xff, 0, 0)), &DrawOptions::new());
 
     }
 
@@ -37,4 +35,8 @@ impl Tile for PlayerTile {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::sync::OnceLock;
+use raqote::Color;
+use serde::Deserialize;
+use crate::tile::entity::TileEntityKind;
+use crate::world::MAX_LIGHT;
+
+/// Name of the data file in the assets directory
+pub const FILE: &str = "tiles.toml";
+/// Tile definitions shipped with the game, used when the data file can't be loaded
+const BUILTIN: &str = include_str!("../../assets/tiles.toml");
+/// Tiles softer than this shatter when hit by a heavy projectile
+const FRAGILE_HARDNESS: f32 = 0.4;
+/// Tiles world generation places, which every data file has to define
+pub const REQUIRED: [&str; 4] = ["bedrock", "stone", "dirt", "grass"];
+
+/// The loaded definitions, set once at startup
+pub static REGISTRY: OnceLock<TileRegistry> = OnceLock::new();
+
+/// A kind of tile as declared in the data file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TileDef {
+    pub id: String,
+    color: [u8; 3],
+    sprite: Option<String>,
+    #[serde(default = "default_true")]
+    pub solid: bool,
+    /// Lets light through like air even though the tile is solid
+    #[serde(default)]
+    pub transparent: bool,
+    #[serde(default = "default_one")]
+    pub hardness: f32,
+    /// How much walking entities are slowed, 1 being normal ground and 0 none at all
+    #[serde(default = "default_one")]
+    pub friction: f32,
+    /// Block light the tile gives off, 0 for tiles that do not glow
+    #[serde(default)]
+    pub light: u8,
+    /// Ids of the tiles left behind when it is broken
+    #[serde(default)]
+    pub drops: Vec<String>,
+    /// Tile entity placed along with the tile
+    #[serde(default)]
+    pub entity: Option<TileEntityKind>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+impl TileDef {
+    pub fn color(&self) -> Color {
+        Color::new(255, self.color[0], self.color[1], self.color[2])
+    }
+
+    pub fn is_fragile(&self) -> bool {
+        self.hardness < FRAGILE_HARDNESS
+    }
+
+    /// Name of the atlas sprite to draw
+    pub fn sprite(&self) -> &str {
+        self.sprite.as_deref().unwrap_or(&self.id)
+    }
+}
+
+/// Definitions are unique by id
+impl PartialEq for TileDef {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    tiles: Vec<TileDef>,
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    InvalidId(String),
+    Duplicate(String),
+    Missing(&'static str),
+    UnknownDrop { tile: String, drop: String },
+    TooBright(String),
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Io(e) => write!(f, "could not read tile definitions: {}", e),
+            RegistryError::Parse(e) => write!(f, "invalid tile definitions: {}", e),
+            RegistryError::InvalidId(id) => write!(f, "invalid tile id '{}', use lowercase letters, digits and _", id),
+            RegistryError::Duplicate(id) => write!(f, "tile '{}' is defined twice", id),
+            RegistryError::Missing(id) => write!(f, "tile '{}' has to be defined", id),
+            RegistryError::UnknownDrop { tile, drop } => write!(f, "tile '{}' drops unknown tile '{}'", tile, drop),
+            RegistryError::TooBright(id) => write!(f, "tile '{}' gives off more than {} light", id, MAX_LIGHT),
+        }
+    }
+}
+
+impl From<std::io::Error> for RegistryError {
+    fn from(e: std::io::Error) -> Self {
+        RegistryError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for RegistryError {
+    fn from(e: toml::de::Error) -> Self {
+        RegistryError::Parse(e)
+    }
+}
+
+/// Tile definitions by id
+pub struct TileRegistry {
+    defs: Vec<TileDef>,
+    by_id: HashMap<String, usize>,
+}
+
+impl TileRegistry {
+    /// Reads the definitions in the data file at `path`
+    pub fn load(path: &Path) -> Result<TileRegistry, RegistryError> {
+        TileRegistry::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// The definitions shipped with the game
+    pub fn builtin() -> TileRegistry {
+        TileRegistry::parse(BUILTIN).expect("built in tile definitions are valid")
+    }
+
+    fn parse(text: &str) -> Result<TileRegistry, RegistryError> {
+        let manifest: Manifest = toml::from_str(text)?;
+        let mut by_id = HashMap::new();
+        for (i, def) in manifest.tiles.iter().enumerate() {
+            let valid = !def.id.is_empty() && def.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+            // "air" is the empty tile, which has no definition
+            if !valid || def.id == "air" {
+                return Err(RegistryError::InvalidId(def.id.clone()));
+            }
+            if def.light > MAX_LIGHT {
+                return Err(RegistryError::TooBright(def.id.clone()));
+            }
+            if by_id.insert(def.id.clone(), i).is_some() {
+                return Err(RegistryError::Duplicate(def.id.clone()));
+            }
+        }
+        if let Some(missing) = REQUIRED.iter().find(|id| !by_id.contains_key(**id)) {
+            return Err(RegistryError::Missing(missing));
+        }
+        for def in &manifest.tiles {
+            if let Some(drop) = def.drops.iter().find(|drop| !by_id.contains_key(*drop)) {
+                return Err(RegistryError::UnknownDrop { tile: def.id.clone(), drop: drop.clone() });
+            }
+        }
+        Ok(TileRegistry { defs: manifest.tiles, by_id })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TileDef> {
+        self.by_id.get(id).map(|&i| &self.defs[i])
+    }
+}
+
+/// The loaded definitions, the built in ones if none were loaded
+pub fn registry() -> &'static TileRegistry {
+    REGISTRY.get_or_init(TileRegistry::builtin)
+}
+
+pub fn def(id: &str) -> Option<&'static TileDef> {
+    registry().get(id)
+}
+
+/// The definition of a tile world generation relies on, see [`REQUIRED`]
+pub fn required(id: &'static str) -> &'static TileDef {
+    def(id).unwrap_or_else(|| panic!("tile '{}' is required", id))
+}
@@ -0,0 +1,72 @@
+use std::any::Any;
+use std::sync::OnceLock;
+use font_kit::font::Font;
+use raqote::{Color, DrawOptions, DrawTarget, SolidSource, Source};
+use crate::coords::ViewTransform;
+use crate::TilePosition;
+use crate::tile::{Tile, TileType};
+use crate::world::World;
+
+/// Tile kinds registered by scripts, set once every script has loaded
+pub static KINDS: OnceLock<Vec<ScriptTileKind>> = OnceLock::new();
+
+/// A tile kind defined by a script. Its hooks live in the [`crate::script::ScriptHost`].
+#[derive(Clone, Debug)]
+pub struct ScriptTileKind {
+    /// "<script>:<name>", such as "example:sand"
+    pub id: String,
+    pub color: Color,
+    pub solid: bool,
+}
+
+pub fn kind(id: &str) -> Option<&'static ScriptTileKind> {
+    KINDS.get()?.iter().find(|kind| kind.id == id)
+}
+
+pub struct ScriptedTile {
+    kind: &'static ScriptTileKind,
+}
+
+impl ScriptedTile {
+    /// A tile of a registered kind, None if no script registered `id`
+    pub fn new(id: &str) -> Option<Box<ScriptedTile>> {
+        kind(id).map(|kind| Box::new(ScriptedTile { kind }))
+    }
+
+    pub fn kind(&self) -> &'static ScriptTileKind {
+        self.kind
+    }
+}
+
+impl Tile for ScriptedTile {
+    fn render(&self, target: &mut DrawTarget, pos: &TilePosition, _font: &Font, view: &ViewTransform, _tick: u64) {
+        let (x, y, w, h) = view.tile_to_screen(pos);
+        target.fill_rect(x, y, w, h, &Source::Solid(SolidSource::from(self.kind.color)), &DrawOptions::new());
+    }
+
+    /// The script host runs the update hook once the tick is over, as hooks need the world to themselves
+    fn update(&mut self, world: &mut World, pos: &TilePosition) {
+        world.queue_script_update(pos);
+    }
+
+    /// Neighbour changes run the update hook too, so falling tiles start falling
+    fn on_neighbor_changed(&mut self, world: &mut World, pos: &TilePosition, _neighbor: &TilePosition) {
+        world.queue_script_update(pos);
+    }
+
+    fn get_type(&self) -> &TileType {
+        &TileType::Scripted
+    }
+
+    fn is_solid(&self) -> bool {
+        self.kind.solid
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,604 @@
+pub mod input;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use font_kit::font::Font;
+use raqote::{DrawOptions, DrawTarget, Point, SolidSource, Source};
+use crate::ui::input::{UiInput, UiKey};
+
+// An immediate mode UI: widgets are functions called every frame that draw themselves and
+// return what the user did with them. Only focus, the pressed widget and scroll offsets are
+// kept between frames, in `UiState`.
+
+const TEXT_SIZE: f32 = 14.0;
+const LINE_HEIGHT: f32 = 20.0;
+const BUTTON_HEIGHT: f32 = 28.0;
+/// Space between the edge of a widget or panel and what is inside it
+const PADDING: f32 = 6.0;
+/// Space between widgets laid out next to each other
+const SPACING: f32 = 6.0;
+const CHECKBOX_SIZE: f32 = 16.0;
+const SLIDER_WIDTH: f32 = 160.0;
+const SCROLLBAR_WIDTH: f32 = 6.0;
+
+fn color(a: u8, r: u8, g: u8, b: u8) -> Source<'static> {
+    Source::Solid(SolidSource::from_unpremultiplied_argb(a, r, g, b))
+}
+fn text_color() -> Source<'static> { color(0xff, 0xf0, 0xf0, 0xf0) }
+fn panel_color() -> Source<'static> { color(0xd0, 0x18, 0x1c, 0x24) }
+fn widget_color() -> Source<'static> { color(0xff, 0x3a, 0x40, 0x4c) }
+fn hover_color() -> Source<'static> { color(0xff, 0x4e, 0x56, 0x66) }
+fn active_color() -> Source<'static> { color(0xff, 0x5c, 0x88, 0xc4) }
+
+/// A screen space rectangle, x and y being the top left corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    pub fn contains(&self, point: (f32, f32)) -> bool {
+        point.0 >= self.x && point.0 < self.x + self.w && point.1 >= self.y && point.1 < self.y + self.h
+    }
+}
+
+/// Identifies a widget across frames by its label, so labels have to be unique on a screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WidgetId(u64);
+
+/// What the UI remembers between frames
+#[derive(Default)]
+pub struct UiState {
+    /// The widget the mouse was pressed on, until it is released
+    active: Option<WidgetId>,
+    /// The text input receiving typed characters
+    focused: Option<WidgetId>,
+    was_mouse_down: bool,
+    /// First visible row of each scroll list
+    scroll: HashMap<WidgetId, usize>,
+}
+
+impl UiState {
+    pub fn has_focus(&self) -> bool {
+        self.focused.is_some()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Row,
+    Column,
+}
+
+struct Layout {
+    direction: Direction,
+    origin: (f32, f32),
+    /// How far along the direction widgets have been placed
+    along: f32,
+    /// Size of the largest widget across the direction
+    across: f32,
+}
+
+impl Layout {
+    fn size(&self) -> (f32, f32) {
+        match self.direction {
+            Direction::Row => (self.along, self.across),
+            Direction::Column => (self.across, self.along),
+        }
+    }
+}
+
+/// One frame of UI drawn into a target. Widgets are placed one after another in the current
+/// row or column, starting as a column at the origin.
+pub struct Ui<'a> {
+    target: &'a mut DrawTarget,
+    font: &'a Font,
+    input: &'a UiInput,
+    state: &'a mut UiState,
+    layouts: Vec<Layout>,
+    /// Whether a widget took focus this frame, a click anywhere else drops focus
+    focus_taken: bool,
+}
+
+impl<'a> Ui<'a> {
+    pub fn new(target: &'a mut DrawTarget, font: &'a Font, input: &'a UiInput, state: &'a mut UiState, origin: (f32, f32)) -> Ui<'a> {
+        Ui {
+            target,
+            font,
+            input,
+            state,
+            layouts: vec![Layout { direction: Direction::Column, origin, along: 0.0, across: 0.0 }],
+            focus_taken: false,
+        }
+    }
+
+    pub fn input(&self) -> &UiInput {
+        self.input
+    }
+
+    /// Width of text drawn at a size, in pixels
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        text_width(self.font, text, size)
+    }
+
+    fn pressed(&self) -> bool {
+        self.input.mouse_down && !self.state.was_mouse_down
+    }
+
+    fn released(&self) -> bool {
+        !self.input.mouse_down && self.state.was_mouse_down
+    }
+
+    fn id(&self, label: &str) -> WidgetId {
+        let mut hasher = DefaultHasher::new();
+        label.hash(&mut hasher);
+        WidgetId(hasher.finish())
+    }
+
+    /// Takes space for a widget in the current layout
+    pub fn allocate(&mut self, w: f32, h: f32) -> Rect {
+        let layout = self.layouts.last_mut().unwrap();
+        let gap = if layout.along > 0.0 { SPACING } else { 0.0 };
+        match layout.direction {
+            Direction::Row => {
+                let rect = Rect::new(layout.origin.0 + layout.along + gap, layout.origin.1, w, h);
+                layout.along += gap + w;
+                layout.across = layout.across.max(h);
+                rect
+            }
+            Direction::Column => {
+                let rect = Rect::new(layout.origin.0, layout.origin.1 + layout.along + gap, w, h);
+                layout.along += gap + h;
+                layout.across = layout.across.max(w);
+                rect
+            }
+        }
+    }
+
+    /// Empty space in the current layout
+    pub fn space(&mut self, size: f32) {
+        self.allocate(size, size);
+    }
+
+    fn nested(&mut self, direction: Direction, f: impl FnOnce(&mut Ui)) -> Rect {
+        let layout = self.layouts.last().unwrap();
+        let gap = if layout.along > 0.0 { SPACING } else { 0.0 };
+        let origin = match layout.direction {
+            Direction::Row => (layout.origin.0 + layout.along + gap, layout.origin.1),
+            Direction::Column => (layout.origin.0, layout.origin.1 + layout.along + gap),
+        };
+        self.layouts.push(Layout { direction, origin, along: 0.0, across: 0.0 });
+        f(self);
+        let (w, h) = self.layouts.pop().unwrap().size();
+        self.allocate(w, h)
+    }
+
+    /// Lays out the widgets added in `f` left to right
+    pub fn row(&mut self, f: impl FnOnce(&mut Ui)) -> Rect {
+        self.nested(Direction::Row, f)
+    }
+
+    /// Lays out the widgets added in `f` top to bottom
+    pub fn column(&mut self, f: impl FnOnce(&mut Ui)) -> Rect {
+        self.nested(Direction::Column, f)
+    }
+
+    /// A fixed size box with a background, its widgets laid out top to bottom inside
+    pub fn panel(&mut self, w: f32, h: f32, f: impl FnOnce(&mut Ui)) -> Rect {
+        let rect = self.allocate(w, h);
+        self.target.fill_rect(rect.x, rect.y, rect.w, rect.h, &panel_color(), &DrawOptions::new());
+        self.layouts.push(Layout {
+            direction: Direction::Column,
+            origin: (rect.x + PADDING, rect.y + PADDING),
+            along: 0.0,
+            across: 0.0,
+        });
+        f(self);
+        self.layouts.pop();
+        rect
+    }
+
+    /// A panel in the middle of the target, outside of the current layout
+    pub fn centered_panel(&mut self, w: f32, h: f32, f: impl FnOnce(&mut Ui)) -> Rect {
+        let origin = ((self.target.width() as f32 - w) / 2.0, (self.target.height() as f32 - h) / 2.0);
+        self.layouts.push(Layout { direction: Direction::Column, origin, along: 0.0, across: 0.0 });
+        let rect = self.panel(w, h, f);
+        self.layouts.pop();
+        rect
+    }
+
+    fn draw_text(&mut self, text: &str, x: f32, rect: &Rect, source: &Source) {
+        // Center the text vertically, font-kit draws from the baseline
+        let baseline = rect.y + (rect.h + TEXT_SIZE * 0.7) / 2.0;
+        draw_text(self.target, self.font, TEXT_SIZE, text, Point::new(x, baseline), source, &DrawOptions::new());
+    }
+
+    /// Tracks the mouse over a widget, returning whether it is hovered and whether it was clicked
+    fn interact(&mut self, id: WidgetId, rect: &Rect) -> (bool, bool) {
+        let hovered = self.input.mouse.is_some_and(|mouse| rect.contains(mouse));
+        if hovered && self.pressed() {
+            self.state.active = Some(id);
+        }
+        let clicked = hovered && self.released() && self.state.active == Some(id);
+        (hovered, clicked)
+    }
+
+    fn widget_background(&mut self, id: WidgetId, rect: &Rect, hovered: bool) {
+        let source = if self.state.active == Some(id) {
+            active_color()
+        } else if hovered {
+            hover_color()
+        } else {
+            widget_color()
+        };
+        self.target.fill_rect(rect.x, rect.y, rect.w, rect.h, &source, &DrawOptions::new());
+    }
+
+    pub fn label(&mut self, text: &str) -> Rect {
+        let rect = self.allocate(self.text_width(text, TEXT_SIZE), LINE_HEIGHT);
+        self.draw_text(text, rect.x, &rect, &text_color());
+        rect
+    }
+
+    /// A button sized to its text, returning true on the frame it is clicked
+    pub fn button(&mut self, text: &str) -> bool {
+        let width = self.text_width(text, TEXT_SIZE) + PADDING * 4.0;
+        self.button_sized(text, width)
+    }
+
+    /// A button of a fixed width, so a column of them lines up
+    pub fn button_sized(&mut self, text: &str, width: f32) -> bool {
+        let id = self.id(text);
+        let rect = self.allocate(width, BUTTON_HEIGHT);
+        let (hovered, clicked) = self.interact(id, &rect);
+        self.widget_background(id, &rect, hovered);
+        let x = rect.x + (rect.w - self.text_width(text, TEXT_SIZE)) / 2.0;
+        self.draw_text(text, x, &rect, &text_color());
+        clicked
+    }
+
+    /// A box that toggles `value` when clicked, returning true when it changed
+    pub fn checkbox(&mut self, text: &str, value: &mut bool) -> bool {
+        let id = self.id(text);
+        let rect = self.allocate(CHECKBOX_SIZE + PADDING + self.text_width(text, TEXT_SIZE), LINE_HEIGHT);
+        let (hovered, clicked) = self.interact(id, &rect);
+        if clicked {
+            *value = !*value;
+        }
+        let square = Rect::new(rect.x, rect.y + (rect.h - CHECKBOX_SIZE) / 2.0, CHECKBOX_SIZE, CHECKBOX_SIZE);
+        self.widget_background(id, &square, hovered);
+        if *value {
+            self.target.fill_rect(square.x + 4.0, square.y + 4.0, square.w - 8.0, square.h - 8.0, &text_color(), &DrawOptions::new());
+        }
+        self.draw_text(text, rect.x + CHECKBOX_SIZE + PADDING, &rect, &text_color());
+        clicked
+    }
+
+    /// A draggable handle setting `value` within `range`, returning true when it changed
+    pub fn slider(&mut self, text: &str, value: &mut f32, range: RangeInclusive<f32>) -> bool {
+        let id = self.id(text);
+        let label = format!("{}: {:.2}", text, value);
+        let label_width = self.text_width(&label, TEXT_SIZE);
+        let rect = self.allocate(SLIDER_WIDTH + PADDING + label_width, LINE_HEIGHT);
+        let track = Rect::new(rect.x, rect.y, SLIDER_WIDTH, rect.h);
+        let (hovered, _) = self.interact(id, &track);
+        let old = *value;
+        if self.state.active == Some(id) && self.input.mouse_down {
+            if let Some(mouse) = self.input.mouse {
+                let t = ((mouse.0 - track.x) / track.w).clamp(0.0, 1.0);
+                *value = range.start() + (range.end() - range.start()) * t;
+            }
+        }
+        let options = DrawOptions::new();
+        self.target.fill_rect(track.x, track.y + track.h / 2.0 - 2.0, track.w, 4.0, &hover_color(), &options);
+        let t = if range.end() > range.start() { (*value - range.start()) / (range.end() - range.start()) } else { 0.0 };
+        let handle = Rect::new(track.x + t.clamp(0.0, 1.0) * (track.w - 10.0), track.y, 10.0, track.h);
+        self.widget_background(id, &handle, hovered);
+        self.draw_text(&label, track.x + track.w + PADDING, &rect, &text_color());
+        *value != old
+    }
+
+    /// A single line text field. Clicking it gives it focus, typed text goes into `text` and Escape
+    /// drops focus. Returns true on the frame Enter is pressed while it has focus.
+    pub fn text_input(&mut self, id: &str, text: &mut String, width: f32) -> bool {
+        let id = self.id(id);
+        let rect = self.allocate(width, BUTTON_HEIGHT);
+        let (hovered, _) = self.interact(id, &rect);
+        if hovered && self.pressed() {
+            self.state.focused = Some(id);
+            self.focus_taken = true;
+        }
+        let focused = self.state.focused == Some(id);
+        let mut submitted = false;
+        if focused {
+            let input = self.input;
+            text.extend(input.text.iter());
+            for key in input.keys.iter() {
+                match key {
+                    UiKey::Backspace => {
+                        text.pop();
+                    }
+                    UiKey::Enter => submitted = true,
+                    UiKey::Escape => self.state.focused = None,
+                    _ => {}
+                }
+            }
+        }
+        self.widget_background(id, &rect, hovered || focused);
+        let shown = if focused { format!("{}|", text) } else { text.clone() };
+        self.draw_text(&shown, rect.x + PADDING, &rect, &text_color());
+        submitted
+    }
+
+    /// Gives keyboard focus to the text input with this id, such as when a console opens
+    pub fn focus(&mut self, id: &str) {
+        self.state.focused = Some(self.id(id));
+        self.focus_taken = true;
+    }
+
+    /// A list showing `rows` items at a time, scrolled with the mouse wheel. Clicking an item selects it.
+    /// Returns true when the selection changed.
+    pub fn scroll_list(&mut self, id: &str, items: &[String], rows: usize, width: f32, selected: &mut Option<usize>) -> bool {
+        let id = self.id(id);
+        let rect = self.allocate(width, rows as f32 * LINE_HEIGHT);
+        let hovered = self.input.mouse.is_some_and(|mouse| rect.contains(mouse));
+        let max_offset = items.len().saturating_sub(rows);
+        let mut offset = self.state.scroll.get(&id).copied().unwrap_or(0).min(max_offset);
+        if hovered && self.input.scroll != 0.0 {
+            offset = if self.input.scroll > 0.0 { offset.saturating_sub(1) } else { (offset + 1).min(max_offset) };
+        }
+        self.state.scroll.insert(id, offset);
+
+        let options = DrawOptions::new();
+        self.target.fill_rect(rect.x, rect.y, rect.w, rect.h, &widget_color(), &options);
+        let old = *selected;
+        for (row, index) in (offset..items.len().min(offset + rows)).enumerate() {
+            let item = Rect::new(rect.x, rect.y + row as f32 * LINE_HEIGHT, rect.w - SCROLLBAR_WIDTH, LINE_HEIGHT);
+            let item_hovered = self.input.mouse.is_some_and(|mouse| item.contains(mouse));
+            if item_hovered && self.pressed() {
+                *selected = Some(index);
+            }
+            if *selected == Some(index) {
+                self.target.fill_rect(item.x, item.y, item.w, item.h, &active_color(), &options);
+            } else if item_hovered {
+                self.target.fill_rect(item.x, item.y, item.w, item.h, &hover_color(), &options);
+            }
+            self.draw_text(&items[index], item.x + PADDING, &item, &text_color());
+        }
+        if max_offset > 0 {
+            let thumb_height = rect.h * rows as f32 / items.len() as f32;
+            let thumb_y = rect.y + (rect.h - thumb_height) * offset as f32 / max_offset as f32;
+            self.target.fill_rect(rect.x + rect.w - SCROLLBAR_WIDTH, thumb_y, SCROLLBAR_WIDTH, thumb_height, &hover_color(), &options);
+        }
+        *selected != old
+    }
+}
+
+impl Drop for Ui<'_> {
+    /// Finishes the frame, updating what carries over to the next one
+    fn drop(&mut self) {
+        if self.pressed() && !self.focus_taken {
+            self.state.focused = None;
+        }
+        if !self.input.mouse_down {
+            self.state.active = None;
+        }
+        self.state.was_mouse_down = self.input.mouse_down;
+    }
+}
+
+/// Draws text like `DrawTarget::draw_text`, skipping whitespace. font-kit hands the null bitmap
+/// FreeType gives blank glyphs to `slice::from_raw_parts`, so only runs of visible characters are
+/// rasterised, each placed after the width of the text before it.
+pub fn draw_text(target: &mut DrawTarget, font: &Font, size: f32, text: &str, pos: Point, source: &Source, options: &DrawOptions) {
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        if !c.is_whitespace() {
+            start.get_or_insert(i);
+        } else if let Some(start) = start.take() {
+            let x = pos.x + text_width(font, &text[..start], size);
+            target.draw_text(font, size, &text[start..i], Point::new(x, pos.y), source, options);
+        }
+    }
+}
+
+/// Width of text drawn with a font at a size, in pixels
+pub fn text_width(font: &Font, text: &str, size: f32) -> f32 {
+    let units_per_em = font.metrics().units_per_em as f32;
+    text.chars()
+        .filter_map(|c| font.glyph_for_char(c))
+        .filter_map(|glyph| font.advance(glyph).ok())
+        .map(|advance| advance.x() * size / units_per_em)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use font_kit::family_name::FamilyName;
+    use font_kit::properties::Properties;
+    use font_kit::source::SystemSource;
+    use super::*;
+
+    fn font() -> Font {
+        SystemSource::new()
+            .select_best_match(&[FamilyName::SansSerif], &Properties::new())
+            .unwrap()
+            .load()
+            .unwrap()
+    }
+
+    fn mouse(x: f32, y: f32, down: bool) -> UiInput {
+        UiInput { mouse: Some((x, y)), mouse_down: down, ..UiInput::default() }
+    }
+
+    /// Runs one frame of `f` at the top left of a fresh target, returning what it drew
+    fn frame<R>(font: &Font, state: &mut UiState, input: &UiInput, f: impl FnOnce(&mut Ui) -> R) -> (R, DrawTarget) {
+        let mut target = DrawTarget::new(400, 300);
+        let result = {
+            let mut ui = Ui::new(&mut target, font, input, state, (0.0, 0.0));
+            f(&mut ui)
+        };
+        (result, target)
+    }
+
+    fn pixel(target: &DrawTarget, x: usize, y: usize) -> u32 {
+        target.get_data()[y * target.width() as usize + x]
+    }
+
+    #[test]
+    fn text_skips_blank_glyphs() {
+        let font = font();
+        let mut target = DrawTarget::new(400, 40);
+        let white = color(0xff, 0xff, 0xff, 0xff);
+        draw_text(&mut target, &font, 20.0, "  ab  cd ", Point::new(0.0, 30.0), &white, &DrawOptions::new());
+        // Both words are drawn where they would be with the spaces rasterised
+        let start = text_width(&font, "  ab  ", 20.0) as usize;
+        let drawn = |from: usize, to: usize| (from..to).any(|x| (0..40).any(|y| pixel(&target, x, y) != 0));
+        assert!(!drawn(0, text_width(&font, "  ", 20.0) as usize));
+        assert!(drawn(start, start + text_width(&font, "cd", 20.0) as usize));
+    }
+
+    #[test]
+    fn button_clicks_on_release() {
+        let font = font();
+        let mut state = UiState::default();
+        let (clicked, _) = frame(&font, &mut state, &mouse(5.0, 5.0, true), |ui| ui.button("Play"));
+        assert!(!clicked);
+        let (clicked, _) = frame(&font, &mut state, &mouse(5.0, 5.0, false), |ui| ui.button("Play"));
+        assert!(clicked);
+        // Releasing without pressing first does nothing
+        let (clicked, _) = frame(&font, &mut state, &mouse(5.0, 5.0, false), |ui| ui.button("Play"));
+        assert!(!clicked);
+    }
+
+    #[test]
+    fn button_ignores_release_elsewhere() {
+        let font = font();
+        let mut state = UiState::default();
+        frame(&font, &mut state, &mouse(5.0, 5.0, true), |ui| ui.button("Play"));
+        let (clicked, _) = frame(&font, &mut state, &mouse(300.0, 250.0, false), |ui| ui.button("Play"));
+        assert!(!clicked);
+    }
+
+    #[test]
+    fn checkbox_toggles_and_draws_its_mark() {
+        let font = font();
+        let mut state = UiState::default();
+        let mut value = false;
+        frame(&font, &mut state, &mouse(8.0, 10.0, true), |ui| ui.checkbox("Rain", &mut value));
+        let (changed, target) = frame(&font, &mut state, &mouse(8.0, 10.0, false), |ui| ui.checkbox("Rain", &mut value));
+        assert!(changed && value);
+        // The mark fills the middle of the box
+        assert_eq!(pixel(&target, 8, 10), 0xfff0f0f0);
+        let (_, target) = frame(&font, &mut state, &UiInput::default(), |ui| ui.checkbox("Rain", &mut false));
+        assert_ne!(pixel(&target, 8, 10), 0xfff0f0f0);
+    }
+
+    #[test]
+    fn text_input_takes_focus_and_text() {
+        let font = font();
+        let mut state = UiState::default();
+        let mut text = String::new();
+        // Typing before it has focus goes nowhere
+        let typed = UiInput { text: vec!['x'], ..UiInput::default() };
+        frame(&font, &mut state, &typed, |ui| ui.text_input("name", &mut text, 200.0));
+        assert!(text.is_empty() && !state.has_focus());
+
+        frame(&font, &mut state, &mouse(5.0, 5.0, true), |ui| ui.text_input("name", &mut text, 200.0));
+        assert!(state.has_focus());
+        let typed = UiInput { text: vec!['a', 'b', 'c'], keys: vec![UiKey::Backspace], ..UiInput::default() };
+        let (submitted, _) = frame(&font, &mut state, &typed, |ui| ui.text_input("name", &mut text, 200.0));
+        assert_eq!(text, "ab");
+        assert!(!submitted);
+        let enter = UiInput { keys: vec![UiKey::Enter], ..UiInput::default() };
+        let (submitted, _) = frame(&font, &mut state, &enter, |ui| ui.text_input("name", &mut text, 200.0));
+        assert!(submitted);
+    }
+
+    #[test]
+    fn text_input_loses_focus() {
+        let font = font();
+        let mut state = UiState::default();
+        let mut text = String::new();
+        frame(&font, &mut state, &mouse(5.0, 5.0, true), |ui| ui.text_input("name", &mut text, 200.0));
+        frame(&font, &mut state, &mouse(5.0, 5.0, false), |ui| ui.text_input("name", &mut text, 200.0));
+        // Clicking elsewhere
+        frame(&font, &mut state, &mouse(300.0, 250.0, true), |ui| ui.text_input("name", &mut text, 200.0));
+        assert!(!state.has_focus());
+        frame(&font, &mut state, &mouse(300.0, 250.0, false), |ui| ui.focus("name"));
+        assert!(state.has_focus());
+        let escape = UiInput { keys: vec![UiKey::Escape], ..UiInput::default() };
+        frame(&font, &mut state, &escape, |ui| ui.text_input("name", &mut text, 200.0));
+        assert!(!state.has_focus());
+    }
+
+    #[test]
+    fn scroll_list_scrolls_and_selects() {
+        let font = font();
+        let mut state = UiState::default();
+        let items: Vec<String> = (0..10).map(|i| format!("world {}", i)).collect();
+        let mut selected = None;
+        let scroll = UiInput { mouse: Some((5.0, 5.0)), scroll: -1.0, ..UiInput::default() };
+        let (changed, _) = frame(&font, &mut state, &scroll, |ui| ui.scroll_list("saves", &items, 3, 200.0, &mut selected));
+        assert!(!changed);
+        // The first row now shows the second item
+        let (changed, _) = frame(&font, &mut state, &mouse(5.0, 5.0, true), |ui| ui.scroll_list("saves", &items, 3, 200.0, &mut selected));
+        assert!(changed);
+        assert_eq!(selected, Some(1));
+        frame(&font, &mut state, &mouse(5.0, 5.0, false), |_| ());
+        frame(&font, &mut state, &mouse(5.0, 2.0 * LINE_HEIGHT + 5.0, true), |ui| ui.scroll_list("saves", &items, 3, 200.0, &mut selected));
+        assert_eq!(selected, Some(3));
+        // Scrolling can't go past the end
+        let far = UiInput { mouse: Some((5.0, 5.0)), scroll: -1.0, ..UiInput::default() };
+        for _ in 0..20 {
+            frame(&font, &mut state, &far, |ui| ui.scroll_list("saves", &items, 3, 200.0, &mut selected));
+        }
+        frame(&font, &mut state, &mouse(5.0, 2.0 * LINE_HEIGHT + 5.0, true), |ui| ui.scroll_list("saves", &items, 3, 200.0, &mut selected));
+        assert_eq!(selected, Some(9));
+    }
+
+    #[test]
+    fn slider_follows_the_mouse() {
+        let font = font();
+        let mut state = UiState::default();
+        let mut value = 0.0;
+        let (changed, _) = frame(&font, &mut state, &mouse(SLIDER_WIDTH / 2.0, 5.0, true), |ui| ui.slider("Speed", &mut value, 0.0..=10.0));
+        assert!(changed);
+        assert_eq!(value, 5.0);
+        // Dragging past the end of the track stops at the end of the range
+        frame(&font, &mut state, &mouse(SLIDER_WIDTH * 3.0, 5.0, true), |ui| ui.slider("Speed", &mut value, 0.0..=10.0));
+        assert_eq!(value, 10.0);
+        let (changed, _) = frame(&font, &mut state, &mouse(0.0, 5.0, false), |ui| ui.slider("Speed", &mut value, 0.0..=10.0));
+        assert!(!changed);
+    }
+
+    #[test]
+    fn layouts_place_widgets() {
+        let font = font();
+        let mut state = UiState::default();
+        let ((first, second, row), _) = frame(&font, &mut state, &UiInput::default(), |ui| {
+            let column = ui.column(|ui| {
+                ui.allocate(50.0, 10.0);
+                ui.allocate(30.0, 10.0);
+            });
+            let second = ui.allocate(10.0, 10.0);
+            let row = ui.row(|ui| {
+                ui.allocate(20.0, 10.0);
+                ui.allocate(20.0, 15.0);
+            });
+            (column, second, row)
+        });
+        assert_eq!(first, Rect::new(0.0, 0.0, 50.0, 20.0 + SPACING));
+        assert_eq!(second, Rect::new(0.0, first.h + SPACING, 10.0, 10.0));
+        assert_eq!(row, Rect::new(0.0, second.y + second.h + SPACING, 40.0 + SPACING, 15.0));
+    }
+}
@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use minifb::{InputCallback, Key, KeyRepeat, MouseButton, MouseMode, Window};
+
+/// Keys the UI reacts to besides typed text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiKey {
+    Backspace,
+    Enter,
+    Escape,
+    Up,
+    Down,
+    /// The ` / ~ key
+    Backquote,
+}
+
+/// Everything the UI needs to know about input for one frame. Plain data, so tests and
+/// headless runs can build it by hand instead of reading a window.
+#[derive(Clone, Debug, Default)]
+pub struct UiInput {
+    /// Cursor in screen pixels, None when it is outside of the window
+    pub mouse: Option<(f32, f32)>,
+    pub mouse_down: bool,
+    /// Wheel movement this frame, positive is up
+    pub scroll: f32,
+    /// Characters typed this frame
+    pub text: Vec<char>,
+    /// Keys pressed this frame
+    pub keys: Vec<UiKey>,
+}
+
+impl UiInput {
+    /// Reads the input of a window, taking the characters typed since the last frame from `chars`
+    pub fn from_window(window: &Window, chars: &CharQueue) -> UiInput {
+        let keys = window.get_keys_pressed(KeyRepeat::Yes).into_iter()
+            .filter_map(|key| match key {
+                Key::Backspace => Some(UiKey::Backspace),
+                Key::Enter | Key::NumPadEnter => Some(UiKey::Enter),
+                Key::Escape => Some(UiKey::Escape),
+                Key::Up => Some(UiKey::Up),
+                Key::Down => Some(UiKey::Down),
+                Key::Backquote => Some(UiKey::Backquote),
+                _ => None,
+            })
+            .collect();
+        UiInput {
+            mouse: window.get_mouse_pos(MouseMode::Discard),
+            mouse_down: window.get_mouse_down(MouseButton::Left),
+            scroll: window.get_scroll_wheel().map_or(0.0, |(_, y)| y),
+            text: chars.take(),
+            keys,
+        }
+    }
+
+    pub fn pressed(&self, key: UiKey) -> bool {
+        self.keys.contains(&key)
+    }
+}
+
+/// Collects the characters minifb reports between frames. Register a clone with `Window::set_input_callback`.
+#[derive(Clone, Default)]
+pub struct CharQueue(Rc<RefCell<Vec<char>>>);
+
+impl CharQueue {
+    pub fn take(&self) -> Vec<char> {
+        std::mem::take(&mut *self.0.borrow_mut())
+    }
+}
+
+impl InputCallback for CharQueue {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(c) = char::from_u32(uni_char).filter(|c| !c.is_control()) {
+            self.0.borrow_mut().push(c);
+        }
+    }
+}
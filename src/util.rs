@@ -1,13 +1,13 @@
 use rand::Rng;
 use crate::TilePosition;
-use crate::tile::base::TileTexture;
 use crate::tile::TileType;
 
-pub fn get_random_tile_texture() -> TileTexture {
+/// Id of a random tile
+pub fn get_random_tile_id() -> &'static str {
     let mut rng = rand::thread_rng();
     match rng.gen_range(0..=2) {
-        0 => TileTexture::Grass,
-        _ => TileTexture::Stone,
+        0 => "grass",
+        _ => "stone",
     }
 }
 
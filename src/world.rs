@@ -1,29 +1,108 @@
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::cmp::max;
 use std::ops::RangeBounds;
 use std::ptr;
 use std::rc::{Rc};
 use font_kit::font::Font;
 use rand::distributions::uniform::SampleRange;
-use rand::Rng;
-use rand::rngs::ThreadRng;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use raqote::DrawTarget;
-use crate::{GRID_SIZE, TilePosition};
-use crate::entity::Entity;
-use crate::tile::base::{BaseTile, TileTexture};
+use serde::Deserialize;
+use crate::coords::ViewTransform;
+use crate::{TilePosition, TILE_SIZE};
+use crate::ecs::{Bundle, Ecs, EntityId};
+use crate::clock::WorldClock;
+use crate::ecs::system;
+use crate::lighting::{self, LightMap};
+use crate::particles::Particles;
+use crate::entity::{self, Entity};
+use crate::pathfinding::{self, PathOptions};
+use crate::spawning::{SpawnRule, Spawner};
+use crate::tile::base::BaseTile;
+use crate::tile::registry::{self, TileDef};
 use crate::tile::{Tile, TileType};
 use crate::tile::air::EmptyTile;
+use crate::tile::autotile;
+use crate::tile::entity::TileEntity;
+use crate::tile::meta::TileMeta;
+use crate::plugin;
 
 pub struct World {
     self_ref: Option<Rc<RefCell<World>>>,
     tiles: Vec<Vec<Box<dyn Tile>>>,
-    entities: Vec<Rc<RefCell<Box<dyn Entity>>>>
+    ecs: Ecs,
+    entities: Vec<Rc<RefCell<Box<dyn Entity>>>>,
+    seed: u64,
+    /// Randomness used while the world runs, derived from the seed so headless runs are repeatable
+    rng: RefCell<StdRng>,
+    /// The most recent path found through `find_path`, kept for the debug overlay
+    last_path: RefCell<Option<Vec<TilePosition>>>,
+    pub show_paths: bool,
+    spawner: RefCell<Spawner>,
+    light: LightMap,
+    clock: WorldClock,
+    particles: RefCell<Particles>,
+    pub raining: bool,
+    /// Ticks since the world was created, animations advance with this so replays render the same
+    ticks: u64,
+    /// What happened since the events were last taken, for scripts to react to
+    events: Vec<WorldEvent>,
+    /// Metadata of every tile, indexed like `tiles`
+    meta: Vec<Vec<TileMeta>>,
+    tile_entities: HashMap<TilePosition, TileEntity>,
+    /// Tiles due an update, by the tick they are due on
+    scheduled: BTreeMap<u64, Vec<TilePosition>>,
+    /// Tiles to tell about a change next to them, along with the neighbour that changed
+    notifications: Vec<(TilePosition, TilePosition)>,
+    /// Where the tile taken out of the world while its hooks run goes back to, None once it was replaced
+    updating: Option<TilePosition>,
+    /// Tiles replaced or moved so far, to tell whether a tile hook changed the world
+    tile_changes: u64,
+    /// Scripted tiles due their update hook, which the script host runs once the tick is over
+    script_updates: Vec<TilePosition>,
 }
 
-const TILE_LAYERS: [TileTexture; 4] = [TileTexture::Bedrock, TileTexture::Stone, TileTexture::Dirt, TileTexture::Grass];
+/// The brightest light level a tile can have
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Biome {
+    /// Open to the sky
+    Surface,
+    /// Below the topmost solid tile of the column
+    Underground,
+}
+
+/// Events kept until they are taken, later ones are dropped
+const MAX_EVENTS: usize = 4096;
+/// Neighbour notifications handled in a tick, the rest wait for the next one so chain reactions
+/// can't stall a tick
+const MAX_NOTIFICATIONS_PER_TICK: usize = 4096;
+
+/// Something that happened in the world
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorldEvent {
+    TileChanged(TilePosition),
+    EntitySpawned(EntityId),
+}
 
 impl World {
     pub fn new(width: usize, height: usize) -> Rc<RefCell<World>> {
+        World::with_seed(width, height, rand::random())
+    }
+
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Rc<RefCell<World>> {
+        World::build(width, height, seed, |world| {
+            world.generate();
+            plugin::generate(world);
+        })
+    }
+
+    /// Creates an empty world and lets `fill` place its tiles, lighting it once they are all in place
+    pub fn build(width: usize, height: usize, seed: u64, fill: impl FnOnce(&mut World)) -> Rc<RefCell<World>> {
         // Initialize the tile
         let mut rows = vec![];
         for _ in 0..height {
@@ -37,15 +116,106 @@ impl World {
         let mut world = World {
             self_ref: None,
             tiles: rows,
+            meta: vec![vec![TileMeta::default(); width]; height],
+            tile_entities: HashMap::new(),
+            ecs: Ecs::new(),
             entities: vec![],
+            seed,
+            rng: RefCell::new(StdRng::seed_from_u64(seed.wrapping_add(1))),
+            last_path: RefCell::new(None),
+            show_paths: false,
+            spawner: RefCell::new(Spawner::default()),
+            light: LightMap::default(),
+            clock: WorldClock::default(),
+            particles: RefCell::new(Particles::default()),
+            raining: false,
+            ticks: 0,
+            events: vec![],
+            scheduled: BTreeMap::new(),
+            notifications: vec![],
+            updating: None,
+            tile_changes: 0,
+            script_updates: vec![],
         };
 
-        world.generate();
+        fill(&mut world);
+        world.light = LightMap::compute(&world);
+        // Placing the initial tiles is not news to anyone, though they still get their first update
+        world.events.clear();
+        world.notifications.clear();
         let world_ref = Rc::new(RefCell::new(world));
         world_ref.borrow_mut().self_ref = Some(world_ref.clone());
         world_ref
     }
 
+    /// Width in tiles
+    pub fn width(&self) -> usize {
+        self.tiles.first().map_or(0, |row| row.len())
+    }
+
+    /// Height in tiles
+    pub fn height(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Size of the world in pixels
+    pub fn pixel_size(&self) -> (f32, f32) {
+        (self.width() as f32 * TILE_SIZE, self.height() as f32 * TILE_SIZE)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn clock(&self) -> &WorldClock {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut WorldClock {
+        &mut self.clock
+    }
+
+    pub fn rng(&self) -> RefMut<'_, StdRng> {
+        self.rng.borrow_mut()
+    }
+
+    pub fn particles(&self) -> RefMut<'_, Particles> {
+        self.particles.borrow_mut()
+    }
+
+    pub fn set_spawn_rules(&mut self, rules: Vec<SpawnRule>) {
+        self.spawner = RefCell::new(Spawner::new(rules));
+    }
+
+    pub fn ecs(&self) -> &Ecs {
+        &self.ecs
+    }
+
+    pub fn ecs_mut(&mut self) -> &mut Ecs {
+        &mut self.ecs
+    }
+
+    /// Spawns a bundle of components as a new entity
+    pub fn spawn(&mut self, bundle: impl Bundle) -> EntityId {
+        let id = self.ecs.spawn_bundle(bundle);
+        self.push_event(WorldEvent::EntitySpawned(id));
+        id
+    }
+
+    /// Takes the events that happened since the last call
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn push_event(&mut self, event: WorldEvent) {
+        if self.events.len() < MAX_EVENTS {
+            self.events.push(event);
+        }
+    }
 
     pub fn add_entity(&mut self, mut entity: Box<dyn Entity>) -> Rc<RefCell<Box<dyn Entity>>> {
         entity.set_world(Rc::downgrade(self.self_ref.as_ref().unwrap()));
@@ -66,6 +236,14 @@ impl World {
         self.swap_in_tile(pos, replacement_tile)
     }
 
+    /// Removes the tile at position, scattering debris in its colour
+    pub fn break_tile(&mut self, pos: &TilePosition) -> Box<dyn Tile> {
+        if let Some(def) = self.get_def(pos) {
+            self.particles.borrow_mut().debris(pos, def.color(), &mut *self.rng.borrow_mut());
+        }
+        self.remove_tile(pos)
+    }
+
     pub fn get_tile(&self, pos: &TilePosition) -> Option<&Box<dyn Tile>> {
         if let Some(row) = self.tiles.get(pos.1) {
             return row.get(pos.0);
@@ -82,10 +260,26 @@ impl World {
         None
     }
 
-    /// Swaps in tile into position, returning the replaced tile
+    /// Swaps in tile into position, returning the replaced tile. The old tile's metadata and tile
+    /// entity go with it, and the new tile gets the tile entity its definition asks for along with
+    /// an update on the next tick.
     pub fn swap_in_tile(&mut self, pos: &TilePosition, mut tile: Box<dyn Tile>) -> Box<dyn Tile> {
+        if tile.get_type() != &TileType::Empty {
+            self.schedule_tick(pos, 1);
+        }
         let row = self.tiles.get_mut(pos.1).unwrap();
-        std::mem::replace(&mut row[pos.0], tile)
+        let old = std::mem::replace(&mut row[pos.0], tile);
+        // The tile being updated was replaced, so it does not go back
+        if self.updating.as_ref() == Some(pos) {
+            self.updating = None;
+        }
+        self.meta[pos.1][pos.0] = TileMeta::default();
+        self.tile_entities.remove(pos);
+        if let Some(kind) = self.get_def(pos).and_then(|def| def.entity) {
+            self.tile_entities.insert(pos.clone(), TileEntity::new(kind));
+        }
+        self.tile_changed(pos);
+        old
     }
 
     pub fn swap_tile(&mut self, a: &TilePosition, b: &TilePosition) {
@@ -95,10 +289,140 @@ impl World {
             let tile_b: *mut Box<dyn Tile> = &mut self.tiles[b.1][b.0];
             ptr::swap(tile_a, tile_b);
         }
+        // Their state moves along with them
+        let meta_a = self.meta[a.1][a.0];
+        self.meta[a.1][a.0] = std::mem::replace(&mut self.meta[b.1][b.0], meta_a);
+        let entity_a = self.tile_entities.remove(a);
+        if let Some(entity_b) = self.tile_entities.remove(b) {
+            self.tile_entities.insert(a.clone(), entity_b);
+        }
+        if let Some(entity_a) = entity_a {
+            self.tile_entities.insert(b.clone(), entity_a);
+        }
+        if self.updating.as_ref() == Some(a) {
+            self.updating = Some(b.clone());
+        } else if self.updating.as_ref() == Some(b) {
+            self.updating = Some(a.clone());
+        }
+        self.tile_changed(a);
+        self.tile_changed(b);
+    }
+
+    /// Updates what depends on the neighbourhood of a tile after it was replaced, and lets its
+    /// neighbours know on the next tick
+    fn tile_changed(&mut self, pos: &TilePosition) {
+        self.tile_changes += 1;
+        self.refresh_around(pos);
+        for neighbour in autotile::neighbours(pos) {
+            self.notifications.push((neighbour, pos.clone()));
+        }
+        self.push_event(WorldEvent::TileChanged(pos.clone()));
+    }
+
+    fn refresh_around(&mut self, pos: &TilePosition) {
+        autotile::refresh_around(self, pos);
+        let mut light = std::mem::take(&mut self.light);
+        light.update_around(self, pos);
+        self.light = light;
+    }
+
+    /// Has the tile at position updated `delay` ticks from now, next tick at the earliest.
+    /// Scheduled ticks are not saved, though every tile of a loaded world gets one.
+    pub fn schedule_tick(&mut self, pos: &TilePosition, delay: u64) {
+        let due = self.ticks + delay.max(1);
+        self.scheduled.entry(due).or_default().push(pos.clone());
+    }
+
+    /// Takes the tile at position out of the world while `f` runs on it, putting it back where it
+    /// ended up unless it was replaced in the meantime
+    fn run_tile(&mut self, pos: &TilePosition, f: impl FnOnce(&mut dyn Tile, &mut World)) {
+        if pos.0 >= self.width() || pos.1 >= self.height() {
+            return;
+        }
+        let mut tile = std::mem::replace(&mut self.tiles[pos.1][pos.0], EmptyTile::new());
+        self.updating = Some(pos.clone());
+        let changes = self.tile_changes;
+        f(tile.as_mut(), self);
+        if let Some(end) = self.updating.take() {
+            self.tiles[end.1][end.0] = tile;
+            // Changes made by the hook were worked out with air in its place
+            if self.tile_changes != changes {
+                self.refresh_around(&end);
+            }
+        }
+    }
+
+    /// Has the script host run the update hook of the scripted tile at position after this tick
+    pub fn queue_script_update(&mut self, pos: &TilePosition) {
+        self.script_updates.push(pos.clone());
+    }
+
+    /// Takes the scripted tiles queued since the last call
+    pub fn take_script_updates(&mut self) -> Vec<TilePosition> {
+        std::mem::take(&mut self.script_updates)
+    }
+
+    /// Metadata of the tile at position, the default outside of the world
+    pub fn meta(&self, pos: &TilePosition) -> TileMeta {
+        self.meta.get(pos.1).and_then(|row| row.get(pos.0)).copied().unwrap_or_default()
+    }
+
+    /// Sets the metadata of the tile at position, returning false outside of the world
+    pub fn set_meta(&mut self, pos: &TilePosition, meta: TileMeta) -> bool {
+        match self.meta.get_mut(pos.1).and_then(|row| row.get_mut(pos.0)) {
+            Some(slot) => {
+                *slot = meta;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn tile_entity(&self, pos: &TilePosition) -> Option<&TileEntity> {
+        self.tile_entities.get(pos)
+    }
+
+    pub fn tile_entity_mut(&mut self, pos: &TilePosition) -> Option<&mut TileEntity> {
+        self.tile_entities.get_mut(pos)
+    }
+
+    pub fn tile_entities(&self) -> impl Iterator<Item = (&TilePosition, &TileEntity)> {
+        self.tile_entities.iter()
+    }
+
+    /// The definition of the tile at position, if it is a base tile
+    pub fn get_def(&self, pos: &TilePosition) -> Option<&'static TileDef> {
+        self.get_tile(pos)
+            .and_then(|t| t.as_any().downcast_ref::<BaseTile>())
+            .map(|t| t.def())
+    }
+
+    /// The y of the topmost solid tile in the column, if there is any
+    pub fn surface_height(&self, x: usize) -> Option<usize> {
+        (0..self.tiles.len()).rev().find(|y| self.is_occupied(&TilePosition(x, *y)))
+    }
+
+    pub fn biome_at(&self, pos: &TilePosition) -> Biome {
+        match self.surface_height(pos.0) {
+            Some(surface) if pos.1 < surface => Biome::Underground,
+            _ => Biome::Surface,
+        }
+    }
+
+    /// How bright the tile is right now, from 0 to `MAX_LIGHT`. Sky light dims at night.
+    pub fn light_at(&self, pos: &TilePosition) -> u8 {
+        lighting::level(self.light.sky_at(pos), self.light.block_at(pos), self.clock.sky_intensity())
     }
 
     pub fn is_occupied(&self, pos: &TilePosition) -> bool {
-        self.get_tile(pos).is_some_and(|t| t.get_type() != &TileType::Empty)
+        self.get_tile(pos).is_some_and(|t| t.is_solid())
+    }
+
+    /// Finds a walkable path between two tiles, remembering it for the path overlay
+    pub fn find_path(&self, start: &TilePosition, goal: &TilePosition, options: &PathOptions) -> Option<Vec<TilePosition>> {
+        let path = pathfinding::find_path(self, start, goal, options);
+        *self.last_path.borrow_mut() = path.clone();
+        path
     }
 
     /// Sets the tile at position, returning a reference to it.
@@ -110,7 +434,7 @@ impl World {
 
     /// Moves a tile from pos 'from' to 'to'. Returns false if out of bounds
     pub fn mv_tile(&mut self, from: &TilePosition, to: &TilePosition) -> bool {
-        if to.0 >= GRID_SIZE || to.1 >= GRID_SIZE {
+        if to.0 >= self.width() || to.1 >= self.height() {
             return false;
         } else if self.is_occupied(to) {
             return false;
@@ -142,7 +466,7 @@ impl World {
         //     for x in 1..GRID_SIZE {
         //         let tile_type = util::get_random_tile_type();
         //         if tile_type != TileType::Empty {
-        //             let tile = BaseTile::new(util::get_random_tile_texture());
+        //             let tile = BaseTile::named(util::get_random_tile_id()).unwrap();
         //             let pos = Position(x, y);
         //             self.set_tile(&pos, tile);
         //         }
@@ -150,19 +474,20 @@ impl World {
         // }
 
         // Make the floor
-        let mut rng = rand::thread_rng();
-        for x in 0..GRID_SIZE {
-            let tile = BaseTile::new(TileTexture::Bedrock);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for x in 0..self.width() {
+            let tile = BaseTile::new(registry::required("bedrock"));
             let pos = TilePosition(x, 0);
             self.set_tile(&pos, tile);
         }
         // For each layer, generate N amount of tiles starting from bottom up
-        self._generate_layer(&mut rng, TileTexture::Stone, vec![TileTexture::Bedrock, TileTexture::Stone], 0, (3,5));
-        self._generate_layer(&mut rng, TileTexture::Dirt, vec![TileTexture::Stone, TileTexture::Dirt], 3, (2,4));
-        self._generate_layer(&mut rng, TileTexture::Grass, vec![TileTexture::Dirt, TileTexture::Grass], 4, (1,1));
+        self._generate_layer(&mut rng, "stone", vec!["bedrock", "stone"], 0, (3,5));
+        self._generate_layer(&mut rng, "dirt", vec!["stone", "dirt"], 3, (2,4));
+        self._generate_layer(&mut rng, "grass", vec!["dirt", "grass"], 4, (1,1));
     }
-    fn _generate_layer(&mut self, rng: &mut ThreadRng, texture: TileTexture, valid_bottoms: Vec<TileTexture>, bottom_y: usize, height_bounds: (usize, usize)){
-        for x in 0..GRID_SIZE {
+    fn _generate_layer(&mut self, rng: &mut StdRng, id: &'static str, valid_bottoms: Vec<&str>, bottom_y: usize, height_bounds: (usize, usize)){
+        let def = registry::required(id);
+        for x in 0..self.width() {
             let mut height = rng.gen_range(height_bounds.0..=height_bounds.1);
             let mut min_y = bottom_y;
             while height > 0 {
@@ -179,36 +504,95 @@ impl World {
                     let bottom_base = bottom_tile.as_any()
                         .downcast_ref::<BaseTile>()
                         .unwrap();
-                    if !valid_bottoms.contains(bottom_base.get_texture()) {
+                    if !valid_bottoms.contains(&bottom_base.def().id.as_str()) {
                         continue;
                     }
                 }
 
-                let tile = BaseTile::new(texture.clone());
+                let tile = BaseTile::new(def);
                 self.set_tile(&pos, tile);
             }
         }
     }
-    /// Renders every tile
-    pub fn render(&self, target: &mut DrawTarget, font: &Font) {
-        for y in 0..self.tiles.len() {
-            for x in 0..self.tiles[y].len() {
+    /// Renders the sky, then the tiles and entities that are in view
+    pub fn render(&self, target: &mut DrawTarget, font: &Font, view: &ViewTransform) {
+        self.clock.render_sky(target);
+        let (columns, rows) = view.visible_tiles((self.width(), self.height()));
+        for y in rows {
+            for x in columns.clone() {
                 let tile = &self.tiles[y][x];
-                tile.render(target, &TilePosition(x, y), font);
+                tile.render(target, &TilePosition(x, y), font, view, self.ticks);
             }
         }
 
+        if self.show_paths {
+            if let Some(path) = self.last_path.borrow().as_ref() {
+                pathfinding::render_path(target, path, view);
+            }
+        }
+
+        system::render::run(&self.ecs, target, font, view, self.ticks);
         for ent in self.entities.iter() {
-            ent.borrow().render(target, font);
+            ent.borrow().render(target, font, view);
         }
+        self.particles.borrow().render(target, view);
+        self.light.render(target, view, self.clock.sky_intensity());
     }
 
     pub fn update(&mut self) {
-        for y in 0..self.tiles.len() {
-            for x in 0..self.tiles[y].len() {
-                let tile = self.tiles.get_mut(y).unwrap().get_mut(x).unwrap();
-                tile.update();
+        self.ticks += 1;
+        self.clock.advance();
+        // Only tiles with a tick due or a changed neighbour are updated
+        let later = self.scheduled.split_off(&(self.ticks + 1));
+        let due = std::mem::replace(&mut self.scheduled, later);
+        let mut seen = HashSet::new();
+        for pos in due.into_values().flatten() {
+            if seen.insert(pos.clone()) {
+                self.run_tile(&pos, |tile, world| tile.update(world, &pos));
+            }
+        }
+        // Notifications made while handling these are handled too, up to the limit
+        let mut budget = MAX_NOTIFICATIONS_PER_TICK;
+        while budget > 0 && !self.notifications.is_empty() {
+            let mut batch = std::mem::take(&mut self.notifications);
+            let mut rest = batch.split_off(budget.min(batch.len()));
+            budget -= batch.len();
+            for (pos, neighbour) in batch {
+                self.run_tile(&pos, |tile, world| tile.on_neighbor_changed(world, &pos, &neighbour));
             }
+            rest.append(&mut self.notifications);
+            self.notifications = rest;
+        }
+        for entity in self.tile_entities.values_mut() {
+            entity.update();
+        }
+
+        system::ai::run(&self.ecs, self);
+        system::physics::run(&self.ecs, self);
+        system::animation::run(&self.ecs, self);
+        let impacts = system::projectile::run(&self.ecs, self);
+        for pos in impacts.broken_tiles.iter() {
+            self.break_tile(pos);
+        }
+        for id in impacts.despawned {
+            self.ecs.despawn(id);
+        }
+        system::contact::run(&self.ecs);
+        system::melee::run(&self.ecs);
+        system::damage::run(&mut self.ecs);
+        system::floating_text::run(&mut self.ecs);
+
+        if self.raining {
+            self.particles.borrow_mut().rain(self.pixel_size(), &mut *self.rng.borrow_mut());
+        }
+        self.particles.borrow_mut().update(self);
+
+        let cycle = self.spawner.borrow_mut().update(self);
+        for id in cycle.despawned {
+            self.ecs.despawn(id);
+        }
+        for (kind, pos) in cycle.spawned {
+            entity::spawn(self, kind, pos);
         }
 
         for ent in self.entities.iter() {